@@ -0,0 +1,817 @@
+use crate::{
+    api_model::{
+        AggregateLevel, Direction, GraphData, MetricKind, NodeMetrics, MAX_DEPTH_LIMIT,
+        MAX_PATHS_LIMIT,
+    },
+    diff::{path_matches, FileChange},
+    model::NodeKind,
+};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+/// 按 id 排序的后继节点, 保证同样的输入每次得到同样的结果
+fn sorted_targets<'a>(edges: &'a HashMap<String, HashSet<String>>, id: &str) -> Vec<&'a String> {
+    let mut targets: Vec<&String> = edges
+        .get(id)
+        .map(|tos| tos.iter().collect())
+        .unwrap_or_default();
+    targets.sort();
+    targets
+}
+
+/// `from` 到 `to` 的最短调用路径, `within` 不为空时只经过其中的节点
+pub fn shortest_path(
+    data: &GraphData,
+    from: &str,
+    to: &str,
+    within: Option<&HashSet<String>>,
+) -> Option<Vec<String>> {
+    let allowed = |id: &str| within.map(|set| set.contains(id)).unwrap_or(true);
+    if !allowed(from) || !allowed(to) {
+        return None;
+    }
+    let mut father: HashMap<&str, &str> = HashMap::new();
+    let mut now_deque = VecDeque::new();
+    now_deque.push_back(from);
+    father.insert(from, from);
+    while let Some(now_id) = now_deque.pop_front() {
+        if now_id == to {
+            let mut path = vec![to.to_owned()];
+            let mut cur = to;
+            while cur != from {
+                cur = father[cur];
+                path.push(cur.to_owned());
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in sorted_targets(&data.edge_from_to, now_id) {
+            if father.contains_key(next.as_str()) || !allowed(next) {
+                continue;
+            }
+            father.insert(next, now_id);
+            now_deque.push_back(next);
+        }
+    }
+    None
+}
+
+/// 每个节点到 `to` 的最短距离, 只统计 `max_depth` 以内
+fn distance_to(
+    data: &GraphData,
+    to: &str,
+    max_depth: usize,
+    within: Option<&HashSet<String>>,
+) -> HashMap<String, usize> {
+    let mut dist = HashMap::new();
+    let mut now_deque = VecDeque::new();
+    dist.insert(to.to_owned(), 0);
+    now_deque.push_back(to.to_owned());
+    while let Some(now_id) = now_deque.pop_front() {
+        let d = dist[&now_id];
+        if d >= max_depth {
+            continue;
+        }
+        if let Some(fathers) = data.edge_to_from.get(&now_id) {
+            for father in fathers {
+                if dist.contains_key(father)
+                    || !within.map(|set| set.contains(father)).unwrap_or(true)
+                {
+                    continue;
+                }
+                dist.insert(father.clone(), d + 1);
+                now_deque.push_back(father.clone());
+            }
+        }
+    }
+    dist
+}
+
+/// `from` 到 `to` 的所有简单路径, 路径长度不超过 `max_depth` 条边, 最多 `max_paths` 条,
+/// 两者分别不超过 `MAX_DEPTH_LIMIT` 和 `MAX_PATHS_LIMIT`. 返回的 bool 表示是否因为 `max_paths` 被截断
+pub fn all_simple_paths(
+    data: &GraphData,
+    from: &str,
+    to: &str,
+    max_depth: usize,
+    max_paths: usize,
+    within: Option<&HashSet<String>>,
+) -> (Vec<Vec<String>>, bool) {
+    let (max_depth, max_paths) = (
+        max_depth.min(MAX_DEPTH_LIMIT),
+        max_paths.min(MAX_PATHS_LIMIT),
+    );
+    let mut paths = Vec::new();
+    let allowed = |id: &str| within.map(|set| set.contains(id)).unwrap_or(true);
+    if !allowed(from) || !allowed(to) || max_paths == 0 {
+        return (paths, false);
+    }
+    let dist = distance_to(data, to, max_depth, within);
+    if !dist.contains_key(from) {
+        return (paths, false);
+    }
+    let mut path = vec![from.to_owned()];
+    let mut on_path: HashSet<String> = HashSet::from([from.to_owned()]);
+    // 每一层待访问的后继, 倒序存放以便按 id 升序弹出
+    let mut first_targets = sorted_targets(&data.edge_from_to, from);
+    first_targets.reverse();
+    let mut stack = vec![first_targets];
+    while let Some(targets) = stack.last_mut() {
+        let Some(next) = targets.pop() else {
+            stack.pop();
+            if let Some(id) = path.pop() {
+                on_path.remove(&id);
+            }
+            continue;
+        };
+        if on_path.contains(next) {
+            continue;
+        }
+        // 剩余步数不足以到达 to 的分支直接剪掉
+        match dist.get(next) {
+            Some(d) if path.len() + d <= max_depth => {}
+            _ => continue,
+        }
+        if next == to {
+            let mut found = path.clone();
+            found.push(next.clone());
+            paths.push(found);
+            if paths.len() >= max_paths {
+                return (paths, true);
+            }
+            continue;
+        }
+        path.push(next.clone());
+        on_path.insert(next.clone());
+        let mut next_targets = sorted_targets(&data.edge_from_to, next);
+        next_targets.reverse();
+        stack.push(next_targets);
+    }
+    (paths, false)
+}
+
+/// 从多个起点出发按方向做 BFS, 返回每个可达节点到最近起点的距离.
+/// `depth` 为空时不限制层数
+pub fn neighbourhood(
+    data: &GraphData,
+    seeds: &[String],
+    depth: Option<usize>,
+    direction: Direction,
+) -> HashMap<String, usize> {
+    let mut dist = HashMap::new();
+    let mut now_deque = VecDeque::new();
+    for seed in seeds {
+        if dist.insert(seed.clone(), 0).is_none() {
+            now_deque.push_back(seed.clone());
+        }
+    }
+    while let Some(now_id) = now_deque.pop_front() {
+        let d = dist[&now_id];
+        if depth.map(|depth| d >= depth).unwrap_or(false) {
+            continue;
+        }
+        let mut nexts = Vec::new();
+        if matches!(direction, Direction::Callees | Direction::Both) {
+            nexts.extend(sorted_targets(&data.edge_from_to, &now_id));
+        }
+        if matches!(direction, Direction::Callers | Direction::Both) {
+            nexts.extend(sorted_targets(&data.edge_to_from, &now_id));
+        }
+        for next in nexts {
+            if dist.contains_key(next) {
+                continue;
+            }
+            dist.insert(next.clone(), d + 1);
+            now_deque.push_back(next.clone());
+        }
+    }
+    dist
+}
+
+/// Tarjan 求强连通分量, 只返回节点数大于 1 或带自环的分量(即存在递归的部分).
+/// 分量内节点按 id 排序, 分量按大小降序排序
+pub fn strongly_connected_components(data: &GraphData) -> Vec<Vec<String>> {
+    cycle_components(&data.edge_from_to)
+}
+
+/// 任意邻接表上的 [`strongly_connected_components`], 聚合视图在分组之间的边上使用
+pub fn cycle_components(edge_from_to: &HashMap<String, HashSet<String>>) -> Vec<Vec<String>> {
+    let mut ids: Vec<&String> = edge_from_to
+        .iter()
+        .flat_map(|(from, tos)| std::iter::once(from).chain(tos.iter()))
+        .collect();
+    ids.sort();
+    ids.dedup();
+
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut low: HashMap<&str, usize> = HashMap::new();
+    let mut on_stack: HashSet<&str> = HashSet::new();
+    let mut stack: Vec<&str> = Vec::new();
+    let mut components = Vec::new();
+    for root in ids {
+        if index.contains_key(root.as_str()) {
+            continue;
+        }
+        // 用显式栈模拟递归, 每一帧为 (节点, 剩余后继)
+        let mut call_stack: Vec<(&str, Vec<&String>)> = Vec::new();
+        let next_index = index.len();
+        index.insert(root, next_index);
+        low.insert(root, next_index);
+        stack.push(root);
+        on_stack.insert(root);
+        let mut targets = sorted_targets(edge_from_to, root);
+        targets.reverse();
+        call_stack.push((root, targets));
+        while let Some((now_id, targets)) = call_stack.last_mut() {
+            let now_id: &str = now_id;
+            if let Some(next) = targets.pop() {
+                let next = next.as_str();
+                if !index.contains_key(next) {
+                    let next_index = index.len();
+                    index.insert(next, next_index);
+                    low.insert(next, next_index);
+                    stack.push(next);
+                    on_stack.insert(next);
+                    let mut next_targets = sorted_targets(edge_from_to, next);
+                    next_targets.reverse();
+                    call_stack.push((next, next_targets));
+                } else if on_stack.contains(next) {
+                    let l = low[now_id].min(index[next]);
+                    low.insert(now_id, l);
+                }
+                continue;
+            }
+            call_stack.pop();
+            if let Some((father, _)) = call_stack.last() {
+                let l = low[*father].min(low[now_id]);
+                low.insert(father, l);
+            }
+            if low[now_id] == index[now_id] {
+                let mut component = Vec::new();
+                while let Some(id) = stack.pop() {
+                    on_stack.remove(id);
+                    component.push(id.to_owned());
+                    if id == now_id {
+                        break;
+                    }
+                }
+                let self_loop = edge_from_to
+                    .get(now_id)
+                    .map(|tos| tos.contains(now_id))
+                    .unwrap_or(false);
+                if component.len() > 1 || self_loop {
+                    component.sort();
+                    components.push(component);
+                }
+            }
+        }
+    }
+    components.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+    components
+}
+
+/// 节点的祖先(不含自身), 由近到远. 最多走 `node_parent.len()` 步, 畸形 dot 中的循环父子关系不会导致死循环
+pub fn ancestors<'a>(data: &'a GraphData, id: &'a str) -> impl Iterator<Item = &'a str> {
+    std::iter::successors(data.node_parent.get(id), |id| {
+        data.node_parent.get(id.as_str())
+    })
+    .map(String::as_str)
+    .take(data.node_parent.len())
+}
+
+/// 节点所在的文件节点 id, 文件节点返回自身
+pub fn file_of<'a>(data: &'a GraphData, id: &'a str) -> &'a str {
+    ancestors(data, id).last().unwrap_or(id)
+}
+
+/// 节点最外层的已折叠祖先(含自身), 没有折叠时返回自身
+pub fn collapsed_ancestor<'a>(
+    data: &'a GraphData,
+    collapsed: &HashSet<String>,
+    id: &'a str,
+) -> &'a str {
+    std::iter::once(id)
+        .chain(ancestors(data, id))
+        .filter(|id| collapsed.contains(*id))
+        .last()
+        .unwrap_or(id)
+}
+
+/// 节点自身的名字, 即 `node_id_to_name` 中冒号分隔的最后一段
+pub fn short_name(name: &str) -> &str {
+    name.split(':').rfind(|v| !v.is_empty()).unwrap_or_default()
+}
+
+/// 去掉 `node_id_to_name` 中的冒号分隔, 用于展示文件路径
+pub fn display_name(data: &GraphData, id: &str) -> String {
+    data.node_id_to_name
+        .get(id)
+        .map(|name| short_name(name).to_owned())
+        .unwrap_or_else(|| id.to_owned())
+}
+
+/// 只支持 `*` 通配符的匹配
+pub fn wildcard_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if text.len() < first.len() + last.len() || !text.starts_with(first) || !text.ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// 函数/方法节点: 不是文件也不包含子节点
+pub fn function_ids(data: &GraphData) -> Vec<&String> {
+    let containers: HashSet<&String> = data.node_parent.values().collect();
+    let mut ids: Vec<&String> = data
+        .node_id_to_name
+        .keys()
+        .filter(|id| {
+            !id.is_empty() && data.node_parent.contains_key(*id) && !containers.contains(id)
+        })
+        .collect();
+    ids.sort();
+    ids
+}
+
+/// 从入口函数出发不可达的函数
+pub fn unreachable_functions(data: &GraphData, entries: &[String]) -> Vec<String> {
+    let reached = neighbourhood(data, entries, None, Direction::Callees);
+    function_ids(data)
+        .into_iter()
+        .filter(|id| !reached.contains_key(*id))
+        .cloned()
+        .collect()
+}
+
+fn metric_node_ids(data: &GraphData) -> Vec<&String> {
+    let mut ids: Vec<&String> = function_ids(data);
+    ids.extend(
+        data.edge_from_to
+            .iter()
+            .flat_map(|(from, tos)| std::iter::once(from).chain(tos.iter())),
+    );
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+/// Brandes 算法计算有向图的介数中心性
+fn betweenness(succ: &[Vec<usize>]) -> Vec<f64> {
+    let n = succ.len();
+    let mut centrality = vec![0.0; n];
+    for s in 0..n {
+        let mut stack = Vec::new();
+        let mut preds: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut sigma = vec![0.0_f64; n];
+        let mut dist: Vec<i64> = vec![-1; n];
+        sigma[s] = 1.0;
+        dist[s] = 0;
+        let mut now_deque = VecDeque::from([s]);
+        while let Some(v) = now_deque.pop_front() {
+            stack.push(v);
+            for &w in &succ[v] {
+                if dist[w] < 0 {
+                    dist[w] = dist[v] + 1;
+                    now_deque.push_back(w);
+                }
+                if dist[w] == dist[v] + 1 {
+                    sigma[w] += sigma[v];
+                    preds[w].push(v);
+                }
+            }
+        }
+        let mut delta = vec![0.0_f64; n];
+        while let Some(w) = stack.pop() {
+            for &v in &preds[w] {
+                delta[v] += sigma[v] / sigma[w] * (1.0 + delta[w]);
+            }
+            if w != s {
+                centrality[w] += delta[w];
+            }
+        }
+    }
+    centrality
+}
+
+const PAGERANK_DAMPING: f64 = 0.85;
+const PAGERANK_ITERATIONS: usize = 50;
+
+/// 调用关系上的 PageRank, 被越多重要函数调用的函数得分越高
+fn pagerank(succ: &[Vec<usize>]) -> Vec<f64> {
+    let n = succ.len();
+    if n == 0 {
+        return Vec::new();
+    }
+    let mut rank = vec![1.0 / n as f64; n];
+    for _ in 0..PAGERANK_ITERATIONS {
+        let dangling: f64 = (0..n)
+            .filter(|&v| succ[v].is_empty())
+            .map(|v| rank[v])
+            .sum();
+        let base = (1.0 - PAGERANK_DAMPING) / n as f64 + PAGERANK_DAMPING * dangling / n as f64;
+        let mut next = vec![base; n];
+        for v in 0..n {
+            if succ[v].is_empty() {
+                continue;
+            }
+            let share = PAGERANK_DAMPING * rank[v] / succ[v].len() as f64;
+            for &w in &succ[v] {
+                next[w] += share;
+            }
+        }
+        rank = next;
+    }
+    rank
+}
+
+/// 从 `start` 出发可达的节点数(不含自身), `seen` 为复用的访问标记, 以 `start + 1` 作为本轮标记
+fn reach_count(
+    adj: &[Vec<usize>],
+    start: usize,
+    seen: &mut [usize],
+    queue: &mut VecDeque<usize>,
+) -> usize {
+    let mark = start + 1;
+    seen[start] = mark;
+    queue.push_back(start);
+    let mut count = 0;
+    while let Some(v) = queue.pop_front() {
+        for &w in &adj[v] {
+            if seen[w] != mark {
+                seen[w] = mark;
+                count += 1;
+                queue.push_back(w);
+            }
+        }
+    }
+    count
+}
+
+/// 自调用不计入任何指标, 扇入扇出与可达数、中心性使用同一份邻接表
+fn compute_metrics(data: &GraphData) -> HashMap<String, NodeMetrics> {
+    let ids = metric_node_ids(data);
+    let index: HashMap<&str, usize> = ids
+        .iter()
+        .enumerate()
+        .map(|(i, id)| (id.as_str(), i))
+        .collect();
+    let succ: Vec<Vec<usize>> = ids
+        .iter()
+        .map(|id| {
+            sorted_targets(&data.edge_from_to, id)
+                .into_iter()
+                .filter(|to| *to != *id)
+                .filter_map(|to| index.get(to.as_str()).copied())
+                .collect()
+        })
+        .collect();
+    let mut pred: Vec<Vec<usize>> = vec![Vec::new(); ids.len()];
+    for (v, tos) in succ.iter().enumerate() {
+        for &w in tos {
+            pred[w].push(v);
+        }
+    }
+    let betweenness = betweenness(&succ);
+    let pagerank = pagerank(&succ);
+    let (mut seen_succ, mut seen_pred) = (vec![0; ids.len()], vec![0; ids.len()]);
+    let mut queue = VecDeque::new();
+    ids.iter()
+        .enumerate()
+        .map(|(i, id)| {
+            let metrics = NodeMetrics {
+                id: (*id).clone(),
+                name: data.node_id_to_name.get(*id).cloned(),
+                fan_in: pred[i].len(),
+                fan_out: succ[i].len(),
+                reach: reach_count(&succ, i, &mut seen_succ, &mut queue),
+                reached_by: reach_count(&pred, i, &mut seen_pred, &mut queue),
+                betweenness: betweenness[i],
+                pagerank: pagerank[i],
+            };
+            ((*id).clone(), metrics)
+        })
+        .collect()
+}
+
+/// 每个节点的指标, 首次访问时计算并缓存在图上. 大图上耗时较长, 需在阻塞线程中调用
+pub fn metrics(data: &GraphData) -> &HashMap<String, NodeMetrics> {
+    data.metrics.get_or_init(|| compute_metrics(data))
+}
+
+pub fn metric_value(metrics: &NodeMetrics, kind: MetricKind) -> f64 {
+    match kind {
+        MetricKind::FanIn => metrics.fan_in as f64,
+        MetricKind::FanOut => metrics.fan_out as f64,
+        MetricKind::Reach => metrics.reach as f64,
+        MetricKind::ReachedBy => metrics.reached_by as f64,
+        MetricKind::Betweenness => metrics.betweenness,
+        MetricKind::Pagerank => metrics.pagerank,
+    }
+}
+
+/// 把节点 id 或名字解析为节点 id. 名字可以是 `node_id_to_name` 中的完整名字或函数名本身,
+/// 同名函数全部返回
+pub fn resolve_nodes(data: &GraphData, id_or_name: &str) -> Vec<String> {
+    if data.node_id_to_name.contains_key(id_or_name) {
+        return vec![id_or_name.to_owned()];
+    }
+    function_ids(data)
+        .into_iter()
+        .filter(|id| {
+            let name = &data.node_id_to_name[*id];
+            name == id_or_name || short_name(name) == id_or_name
+        })
+        .cloned()
+        .collect()
+}
+
+/// 位置与 diff 修改行相交的函数
+pub fn changed_functions(data: &GraphData, changes: &[FileChange]) -> (Vec<String>, Vec<String>) {
+    let mut changed = Vec::new();
+    let mut unmatched_files = Vec::new();
+    let functions = function_ids(data);
+    for change in changes {
+        let mut file_matched = false;
+        for id in &functions {
+            let Some(meta) = data.node_meta.get(*id) else {
+                continue;
+            };
+            if !path_matches(&meta.file, &change.path) {
+                continue;
+            }
+            file_matched = true;
+            let Some(line) = meta.line else {
+                continue;
+            };
+            let end_line = meta.end_line.unwrap_or(usize::MAX);
+            if change.whole_file
+                || change
+                    .lines
+                    .iter()
+                    .any(|changed| *changed >= line && *changed <= end_line)
+            {
+                changed.push((*id).clone());
+            }
+        }
+        if !file_matched {
+            unmatched_files.push(change.path.clone());
+        }
+    }
+    changed.sort();
+    changed.dedup();
+    (changed, unmatched_files)
+}
+
+/// 节点聚合后所属的分组, 文件和容器级别为容器的 node id, 目录级别为目录路径
+pub fn group_of(data: &GraphData, id: &str, level: AggregateLevel) -> String {
+    match level {
+        AggregateLevel::File => file_of(data, id).to_owned(),
+        AggregateLevel::Container => match data.node_meta.get(id).map(|meta| meta.kind) {
+            Some(NodeKind::Function | NodeKind::Method) => data
+                .node_parent
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| id.to_owned()),
+            _ => id.to_owned(),
+        },
+        AggregateLevel::Module => {
+            let file = data
+                .node_meta
+                .get(file_of(data, id))
+                .map(|meta| meta.file.as_str())
+                .unwrap_or_default();
+            match file.rsplit_once('/') {
+                Some((dir, _)) => dir.to_owned(),
+                None => ".".to_owned(),
+            }
+        }
+    }
+}
+
+/// 将节点集合聚合为分组, 返回 (分组 -> 成员数, (from 分组, to 分组) -> 调用次数), 分组内部的调用不计入
+pub fn aggregate(
+    data: &GraphData,
+    nodes: &HashSet<String>,
+    level: AggregateLevel,
+) -> (BTreeMap<String, usize>, BTreeMap<(String, String), usize>) {
+    let mut groups = BTreeMap::new();
+    let mut node_group = HashMap::new();
+    for id in nodes {
+        let group = group_of(data, id, level);
+        *groups.entry(group.clone()).or_insert(0) += 1;
+        node_group.insert(id, group);
+    }
+    let mut edges = BTreeMap::new();
+    for (from, tos) in &data.edge_from_to {
+        let Some(from_group) = node_group.get(from) else {
+            continue;
+        };
+        for to in tos {
+            let Some(to_group) = node_group.get(to) else {
+                continue;
+            };
+            if from_group != to_group {
+                *edges
+                    .entry((from_group.clone(), to_group.clone()))
+                    .or_insert(0) += 1;
+            }
+        }
+    }
+    (groups, edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &str)]) -> GraphData {
+        let mut data = GraphData::default();
+        for (from, to) in edges {
+            for id in [from, to] {
+                data.node_id_to_name.insert(id.to_string(), id.to_string());
+            }
+            data.edge_from_to
+                .entry(from.to_string())
+                .or_default()
+                .insert(to.to_string());
+            data.edge_to_from
+                .entry(to.to_string())
+                .or_default()
+                .insert(from.to_string());
+        }
+        data
+    }
+
+    fn ids(ids: &[&str]) -> Vec<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[test]
+    fn simple_paths_respect_depth_and_count() {
+        let data = graph(&[
+            ("a", "b"),
+            ("b", "d"),
+            ("a", "c"),
+            ("c", "d"),
+            ("a", "d"),
+            ("b", "a"),
+        ]);
+        let (paths, truncated) = all_simple_paths(&data, "a", "d", 2, 10, None);
+        assert_eq!(
+            paths,
+            vec![
+                ids(&["a", "b", "d"]),
+                ids(&["a", "c", "d"]),
+                ids(&["a", "d"])
+            ]
+        );
+        assert!(!truncated);
+        let (paths, _) = all_simple_paths(&data, "a", "d", 1, 10, None);
+        assert_eq!(paths, vec![ids(&["a", "d"])]);
+        let (paths, truncated) = all_simple_paths(&data, "a", "d", 2, 2, None);
+        assert_eq!(paths.len(), 2);
+        assert!(truncated);
+        let within = HashSet::from(["a".to_owned(), "c".to_owned(), "d".to_owned()]);
+        let (paths, _) = all_simple_paths(&data, "a", "d", 2, 10, Some(&within));
+        assert_eq!(paths, vec![ids(&["a", "c", "d"]), ids(&["a", "d"])]);
+    }
+
+    #[test]
+    fn simple_paths_are_capped() {
+        // s -> 5 层, 每层 7 个节点全连接 -> t, 共 7^5 条路径
+        let layers: Vec<Vec<String>> = (0..5)
+            .map(|l| (0..7).map(|i| format!("n{}_{}", l, i)).collect())
+            .collect();
+        let mut edges = Vec::new();
+        for id in &layers[0] {
+            edges.push(("s".to_owned(), id.clone()));
+        }
+        for pair in layers.windows(2) {
+            for from in &pair[0] {
+                for to in &pair[1] {
+                    edges.push((from.clone(), to.clone()));
+                }
+            }
+        }
+        for id in &layers[4] {
+            edges.push((id.clone(), "t".to_owned()));
+        }
+        let edges: Vec<(&str, &str)> = edges
+            .iter()
+            .map(|(from, to)| (from.as_str(), to.as_str()))
+            .collect();
+        let data = graph(&edges);
+        let (paths, truncated) = all_simple_paths(&data, "s", "t", usize::MAX, usize::MAX, None);
+        assert_eq!(paths.len(), MAX_PATHS_LIMIT);
+        assert!(truncated);
+
+        let chain: Vec<String> = (0..=MAX_DEPTH_LIMIT + 1)
+            .map(|i| format!("c{}", i))
+            .collect();
+        let edges: Vec<(&str, &str)> = chain
+            .windows(2)
+            .map(|pair| (pair[0].as_str(), pair[1].as_str()))
+            .collect();
+        let data = graph(&edges);
+        let last = chain.last().unwrap();
+        let (paths, _) = all_simple_paths(&data, "c0", last, usize::MAX, 10, None);
+        assert!(paths.is_empty());
+        let (paths, _) = all_simple_paths(&data, "c1", last, usize::MAX, 10, None);
+        assert_eq!(paths.len(), 1);
+    }
+
+    #[test]
+    fn scc_finds_cycles_and_self_loops() {
+        let data = graph(&[
+            ("a", "b"),
+            ("b", "a"),
+            ("b", "c"),
+            ("c", "d"),
+            ("d", "e"),
+            ("e", "c"),
+            ("f", "f"),
+            ("g", "h"),
+        ]);
+        assert_eq!(
+            strongly_connected_components(&data),
+            vec![ids(&["c", "d", "e"]), ids(&["a", "b"]), ids(&["f"])]
+        );
+    }
+
+    #[test]
+    fn scc_of_acyclic_graph_is_empty() {
+        let data = graph(&[("a", "b"), ("b", "c"), ("a", "c")]);
+        assert!(strongly_connected_components(&data).is_empty());
+    }
+
+    #[test]
+    fn wildcard_patterns() {
+        assert!(wildcard_match("main", "main"));
+        assert!(!wildcard_match("main", "main2"));
+        assert!(wildcard_match("*", ""));
+        assert!(wildcard_match("test_*", "test_parse"));
+        assert!(wildcard_match("*::new", "Foo::new"));
+        assert!(wildcard_match("a*b*c", "axxbyyc"));
+        assert!(!wildcard_match("a*b*c", "acb"));
+        assert!(!wildcard_match("ab*ab", "ab"));
+    }
+
+    #[test]
+    fn cyclic_parents_terminate() {
+        let mut data = graph(&[]);
+        data.node_parent.insert("a".to_owned(), "b".to_owned());
+        data.node_parent.insert("b".to_owned(), "a".to_owned());
+        file_of(&data, "a");
+        collapsed_ancestor(&data, &HashSet::new(), "a");
+    }
+
+    #[test]
+    fn self_loops_are_ignored_by_metrics() {
+        let data = graph(&[("a", "a"), ("a", "b")]);
+        let metrics = compute_metrics(&data);
+        let a = &metrics["a"];
+        assert_eq!((a.fan_in, a.fan_out), (0, 1));
+        assert_eq!((a.reach, a.reached_by), (1, 0));
+        assert_eq!(metrics["b"].fan_in, 1);
+    }
+
+    #[test]
+    fn betweenness_of_star_centre() {
+        let data = graph(&[("a", "hub"), ("b", "hub"), ("hub", "c"), ("hub", "d")]);
+        let metrics = compute_metrics(&data);
+        assert_eq!(metrics["hub"].betweenness, 4.0);
+        for leaf in ["a", "b", "c", "d"] {
+            assert_eq!(metrics[leaf].betweenness, 0.0);
+        }
+    }
+
+    #[test]
+    fn pagerank_of_cycle_is_uniform() {
+        let data = graph(&[("a", "b"), ("b", "c"), ("c", "a")]);
+        let metrics = compute_metrics(&data);
+        for id in ["a", "b", "c"] {
+            assert!((metrics[id].pagerank - 1.0 / 3.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn reach_counts_along_chain() {
+        let data = graph(&[("a", "b"), ("b", "c"), ("c", "d")]);
+        let metrics = compute_metrics(&data);
+        let counts: Vec<(usize, usize)> = ["a", "b", "c", "d"]
+            .iter()
+            .map(|id| (metrics[*id].reach, metrics[*id].reached_by))
+            .collect();
+        assert_eq!(counts, [(3, 0), (2, 1), (1, 2), (0, 3)]);
+    }
+}
@@ -1,21 +1,112 @@
 use crate::{
-    api_model::{AppState, Node, SearchNodeQuery},
+    analysis,
+    api_model::{
+        AggregateLevel, AllPathsQuery, AllPathsResult, AppState, CollapseQuery, Cycle,
+        DeadCodeFile, DeadCodeQuery, DeadCodeReport, DiffNodesResult, DiffSource, Direction,
+        EditorQuery, ElementsFormat, ElementsQuery, ExpandQuery, GraphData, GraphElements,
+        GraphInfo, GraphQuery, ImpactNode, ImpactQuery, ImpactResult, InitGraphQuery, MetricsQuery,
+        Node, NodeAnnotation, NodeDetail, NodeInfo, NodeMeta, NodeMetrics, NodeSource, Operation,
+        PathQuery, RenameDiff, RenderQuery, ReportFormat, SavedView, SavedViewInfo, SavedViewQuery,
+        SearchNodeQuery, SessionInfo, SessionView, SourceQuery, TreeQuery, ViewDiff, ViewDiffQuery,
+        ViewGraph, ViewHistory, ViewState, Workspace, WorkspaceLoadResult, WorkspaceQuery,
+        DEFAULT_CONTEXT_LINES, DEFAULT_MAX_DEPTH, DEFAULT_MAX_PATHS, DEFAULT_METRICS_LIMIT,
+        MAX_CONTEXT_LINES, MAX_SOURCE_LINES,
+    },
+    config::{get_config, resolve_within, Config},
+    diff::{git_diff, parse_unified_diff},
     dot_parse::parse_from_dot,
+    export::{self, DotExporter, ExportOptions, Exporter},
+    layout,
     model::HtmlNode,
+    report,
+    workspace::{file_hash, read_workspace, save_workspace},
 };
 use anyhow::{Context, Result};
 use axum::{
-    extract::Query,
+    extract::{FromRequestParts, Path, Query},
+    http::{header, request::Parts, HeaderName},
+    response::{AppendHeaders, IntoResponse, Response},
     routing::{delete, get, post, put},
     Json, Router,
 };
+use graphviz_rust::cmd::Format;
 use std::{
     collections::{HashMap, HashSet, VecDeque},
-    sync::Mutex,
+    convert::Infallible,
+    sync::{Arc, Mutex, OnceLock},
 };
 
+pub const DEFAULT_GRAPH: &str = "default";
+pub const DEFAULT_SESSION: &str = "default";
+pub const SESSION_HEADER: &str = "x-session-id";
+pub const SESSION_COOKIE: &str = "session_id";
+
 lazy_static::lazy_static! {
-    pub static ref STATE: Mutex<Option<AppState>> = Mutex::new(None);
+    /// graph id -> 已加载的图
+    pub static ref STATE: Mutex<HashMap<String, AppState>> = Mutex::new(HashMap::new());
+    /// session id -> 会话信息
+    pub static ref SESSIONS: Mutex<HashMap<String, SessionInfo>> = Mutex::new(HashMap::new());
+}
+
+/// 从 `/graphs/:graph_id/...` 中提取图 id, 不带前缀的旧路由使用 `DEFAULT_GRAPH`
+pub struct GraphId(pub String);
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for GraphId {
+    type Rejection = Infallible;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let graph_id = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .ok()
+            .and_then(|Path(params)| params.get("graph_id").cloned())
+            .unwrap_or_else(|| DEFAULT_GRAPH.to_owned());
+        Ok(GraphId(graph_id))
+    }
+}
+
+/// 会话 token 从 `x-session-id` 请求头或 `session_id` cookie 中读取, 缺省为 `DEFAULT_SESSION`
+pub struct ViewKey {
+    pub graph_id: String,
+    pub session_id: String,
+}
+
+#[axum::async_trait]
+impl<S: Send + Sync> FromRequestParts<S> for ViewKey {
+    type Rejection = Json<ResponseStatus>;
+
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        let Ok(GraphId(graph_id)) = GraphId::from_request_parts(parts, state).await;
+        let session_id = match session_id_from_parts(parts) {
+            Some(session_id) => session_id,
+            None => DEFAULT_SESSION.to_owned(),
+        };
+        if session_id != DEFAULT_SESSION && !SESSIONS.lock().unwrap().contains_key(&session_id) {
+            return Err(process_resp::<()>(Err(anyhow::anyhow!(
+                "session {} not found",
+                session_id
+            ))));
+        }
+        Ok(ViewKey {
+            graph_id,
+            session_id,
+        })
+    }
+}
+
+fn session_id_from_parts(parts: &Parts) -> Option<String> {
+    if let Some(session_id) = parts
+        .headers
+        .get(SESSION_HEADER)
+        .and_then(|v| v.to_str().ok())
+    {
+        return Some(session_id.trim().to_owned());
+    }
+    let cookies = parts.headers.get(header::COOKIE)?.to_str().ok()?;
+    cookies.split(';').find_map(|cookie| {
+        let (name, value) = cookie.trim().split_once('=')?;
+        (name == SESSION_COOKIE).then(|| value.to_owned())
+    })
 }
 
 pub fn routes() -> Router {
@@ -23,216 +114,992 @@ pub fn routes() -> Router {
         .allow_origin(tower_http::cors::Any)
         .allow_methods(tower_http::cors::Any)
         .allow_headers(tower_http::cors::Any);
+    Router::new()
+        .merge(graph_routes())
+        .route("/graphs", get(list_graphs))
+        .route("/session", post(create_session))
+        .route("/session", get(get_session))
+        .route("/session", delete(delete_session))
+        .route("/session/editor", put(set_session_editor))
+        .nest(
+            "/graphs/:graph_id",
+            graph_routes().route("/", delete(unload_graph)),
+        )
+        .layer(cors)
+}
+
+fn graph_routes() -> Router {
     Router::new()
         .route("/init_graph", get(init_graph))
+        .route("/reload_graph", put(reload_graph))
         .route("/node", get(search_node))
         .route("/node", put(put_node))
         .route("/node_tree", put(put_node_tree))
         .route("/filter_node_father_tree", put(filter_node_father_tree))
+        .route("/expand", put(expand_node))
+        .route("/collapse", put(collapse_node))
+        .route("/collapse", delete(expand_all))
         // .route("/clean_signal", get(clean_signal))
         .route("/node", delete(delete_node))
         .route("/node", post(post_node))
+        .route("/node/annotation", put(annotate_node))
+        .route("/node/:id", get(node_detail))
+        .route("/node/:id/source", get(node_source))
         .route("/graph", get(gen_graph))
-        .layer(cors)
+        .route("/graph.dot", get(gen_graph_dot))
+        .route("/graph/elements", get(graph_elements))
+        .route("/graph.svg", get(render_graph_svg))
+        .route("/graph.png", get(render_graph_png))
+        .route("/cycles", get(list_cycles))
+        .route("/dead_code", get(dead_code))
+        .route("/metrics", get(node_metrics))
+        .route("/impact", post(impact))
+        .route("/diff_nodes", post(diff_nodes))
+        .route("/views", get(list_saved_views))
+        .route("/views", post(save_view))
+        .route("/views", put(switch_view))
+        .route("/views", delete(delete_saved_view))
+        .route("/views/diff", get(diff_views))
+        .route("/shortest_path", get(shortest_path))
+        .route("/shortest_path", put(put_shortest_path))
+        .route("/paths", get(all_paths))
+        .route("/paths", put(put_all_paths))
+        .route("/undo", put(undo))
+        .route("/redo", put(redo))
+        .route("/history", get(history))
+        .route("/workspace/save", post(save_workspace_handler))
+        .route("/workspace/load", put(load_workspace_handler))
+}
+
+fn get_state<'a>(graphs: &'a HashMap<String, AppState>, graph_id: &str) -> Result<&'a AppState> {
+    graphs
+        .get(graph_id)
+        .ok_or(anyhow::anyhow!("graph {} not found", graph_id))
+}
+
+/// 返回共享的解析结果以及当前会话在该图上的视图, 视图按需创建
+fn get_view_mut<'a>(
+    graphs: &'a mut HashMap<String, AppState>,
+    key: &ViewKey,
+) -> Result<(&'a Arc<GraphData>, &'a mut ViewState)> {
+    let state = get_state_mut(graphs, &key.graph_id)?;
+    let session_view = state.views.entry(key.session_id.clone()).or_default();
+    Ok((&state.data, &mut session_view.view))
+}
+
+/// 在当前会话视图上执行一次修改, 视图发生变化时写入操作日志以便撤销
+fn mutate_view<A, T>(
+    key: &ViewKey,
+    op: &str,
+    args: &A,
+    f: impl FnOnce(&GraphData, &mut ViewState) -> Result<T>,
+) -> Result<T>
+where
+    A: serde::Serialize,
+{
+    let mut graphs = STATE.lock().unwrap();
+    let state = get_state_mut(&mut graphs, &key.graph_id)?;
+    let session_view = state.views.entry(key.session_id.clone()).or_default();
+    let snapshot = session_view.view.clone();
+    let ans = f(&state.data, &mut session_view.view)?;
+    if session_view.view != snapshot {
+        session_view.history.record(Operation {
+            op: op.to_owned(),
+            args: serde_json::to_value(args)?,
+            timestamp: now_millis(),
+            snapshot,
+        });
+    }
+    Ok(ans)
+}
+
+fn get_state_mut<'a>(
+    graphs: &'a mut HashMap<String, AppState>,
+    graph_id: &str,
+) -> Result<&'a mut AppState> {
+    graphs
+        .get_mut(graph_id)
+        .ok_or(anyhow::anyhow!("graph {} not found", graph_id))
 }
 
-pub async fn gen_graph() -> Json<ResponseStatus> {
-    process_resp(gen_graph_inner())
+pub async fn gen_graph(key: ViewKey, Query(query): Query<GraphQuery>) -> Json<ResponseStatus> {
+    process_resp(gen_graph_inner(&key, query))
 }
 
+/// 当前视图中要渲染的节点和边, 带上会话选择的编辑器链接
 #[auto_context::auto_context]
-fn gen_graph_inner() -> Result<String> {
-    let mut state = STATE
-        .lock()
-        .unwrap()
-        .as_ref()
-        .ok_or(anyhow::anyhow!("state not found"))?
-        .clone();
-    let mut edges = Vec::new();
-    for (from, tos) in state.edge_from_to {
-        for to in tos {
-            if state.node_set.contains(&to) && state.node_set.contains(&from) {
-                edges.push(format!("{} --> {}", from, to));
-            }
-        }
+fn build_view_graph(
+    key: &ViewKey,
+    highlight_cycles: bool,
+    aggregate: Option<AggregateLevel>,
+) -> Result<ViewGraph> {
+    let (data, view) = {
+        let mut graphs = STATE.lock().unwrap();
+        let (data, view) = get_view_mut(&mut graphs, key)?;
+        (data.clone(), view.clone())
+    };
+    if let Some(level) = aggregate {
+        return Ok(export::aggregated_view_graph(
+            &data,
+            &view,
+            level,
+            highlight_cycles,
+        ));
     }
-    let mut nodes = Vec::new();
-    for id in state.node_set {
-        let mut name = state.node_id_to_name.remove(&id).unwrap_or(id.clone());
-        if let Some(new_name) = state.node_id_to_new_name.remove(&id) {
-            name = new_name;
-        }
-        nodes.push((id, name));
+    let mut graph = export::view_graph(&data, &view, highlight_cycles);
+    let config = get_config();
+    let template = session_editor_template(&config, &key.session_id);
+    for node in graph.nodes.iter_mut() {
+        node.open_url = node_open_url(&config, template.as_deref(), node.meta.as_ref());
+    }
+    Ok(graph)
+}
+
+#[auto_context::auto_context]
+fn gen_graph_inner(key: &ViewKey, query: GraphQuery) -> Result<String> {
+    let graph = build_view_graph(
+        key,
+        query.highlight_cycles.unwrap_or(false),
+        query.aggregate,
+    )?;
+    let options = ExportOptions {
+        direction: query.direction.unwrap_or_default(),
+        group: query.group.unwrap_or(false),
+    };
+    Ok(export::exporter(query.format.unwrap_or_default(), options).export(&graph))
+}
+
+/// React Flow / Cytoscape.js 的结构化节点和边
+#[auto_context::auto_context]
+fn graph_elements_inner(key: &ViewKey, query: ElementsQuery) -> Result<GraphElements> {
+    let mut graph = build_view_graph(
+        key,
+        query.highlight_cycles.unwrap_or(false),
+        query.aggregate,
+    )?;
+    let options = ExportOptions {
+        direction: query.direction.unwrap_or_default(),
+        group: query.group.unwrap_or(false),
+    };
+    if query.layout.unwrap_or(true) {
+        layout::layout(&mut graph, options.direction);
     }
-    let mut dot = String::new();
-    dot.push_str("flowchart TD\n");
-    for (id, name) in nodes {
-        dot.push_str(&format!("{}[{}]\n", id, name));
+    Ok(match query.format.unwrap_or_default() {
+        ElementsFormat::Reactflow => GraphElements::Reactflow(export::react_flow(&graph, options)),
+        ElementsFormat::Cytoscape => GraphElements::Cytoscape(export::cytoscape(&graph, options)),
+    })
+}
+
+pub async fn graph_elements(
+    key: ViewKey,
+    Query(query): Query<ElementsQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(graph_elements_inner(&key, query))
+}
+
+/// 当前视图导出为 Graphviz dot
+#[auto_context::auto_context]
+fn gen_graph_dot_inner(key: &ViewKey, query: GraphQuery) -> Result<String> {
+    let graph = build_view_graph(key, query.highlight_cycles.unwrap_or(false), None)?;
+    Ok(DotExporter(ExportOptions {
+        direction: query.direction.unwrap_or_default(),
+        group: true,
+    })
+    .export(&graph))
+}
+
+pub async fn gen_graph_dot(key: ViewKey, Query(query): Query<GraphQuery>) -> Response {
+    match gen_graph_dot_inner(&key, query) {
+        Ok(dot) => download_response(dot, "text/vnd.graphviz; charset=utf-8", "graph.dot"),
+        Err(e) => process_resp::<()>(Err(e)).into_response(),
     }
-    for edge in edges {
-        dot.push_str(&format!("{}\n", edge));
+}
+
+/// 用 graphviz 将当前视图渲染为 svg/png, 大图不必在浏览器里跑 mermaid
+#[auto_context::auto_context]
+fn render_graph_inner(key: &ViewKey, query: RenderQuery, format: Format) -> Result<Vec<u8>> {
+    let dot = gen_graph_dot_inner(
+        key,
+        GraphQuery {
+            highlight_cycles: query.highlight_cycles,
+            direction: query.direction,
+            ..Default::default()
+        },
+    )?;
+    export::render(dot, query.engine.unwrap_or_default(), format)
+}
+
+pub async fn render_graph_svg(key: ViewKey, Query(query): Query<RenderQuery>) -> Response {
+    match run_blocking(move || render_graph_inner(&key, query, Format::Svg)).await {
+        Ok(svg) => ([(header::CONTENT_TYPE, "image/svg+xml")], svg).into_response(),
+        Err(e) => process_resp::<()>(Err(e)).into_response(),
     }
+}
 
-    Ok(dot)
+pub async fn render_graph_png(key: ViewKey, Query(query): Query<RenderQuery>) -> Response {
+    match run_blocking(move || render_graph_inner(&key, query, Format::Png)).await {
+        Ok(png) => ([(header::CONTENT_TYPE, "image/png")], png).into_response(),
+        Err(e) => process_resp::<()>(Err(e)).into_response(),
+    }
 }
 
 #[auto_context::auto_context]
-fn post_node_inner(query: Node) -> Result<()> {
-    let mut pre_state = STATE.lock().unwrap();
-    let state = pre_state
-        .as_mut()
-        .ok_or(anyhow::anyhow!("state not found"))?;
-    let node_name = query.name.ok_or(anyhow::anyhow!("node name is required"))?;
-    state
-        .node_id_to_new_name
-        .insert(query.id.clone(), node_name);
+fn post_node_inner(key: &ViewKey, query: Node) -> Result<()> {
+    let node_name = query
+        .name
+        .clone()
+        .ok_or(anyhow::anyhow!("node name is required"))?;
+    mutate_view(key, "post_node", &query, |_, view| {
+        view.node_id_to_new_name.insert(query.id.clone(), node_name);
+        Ok(())
+    })
+}
+
+pub async fn post_node(key: ViewKey, Json(query): Json<Node>) -> Json<ResponseStatus> {
+    process_resp(post_node_inner(&key, query))
+}
+
+#[auto_context::auto_context]
+fn list_saved_views_inner(graph_id: &str) -> Result<Vec<SavedViewInfo>> {
+    let graphs = STATE.lock().unwrap();
+    let state = get_state(&graphs, graph_id)?;
+    let mut ans: Vec<SavedViewInfo> = state
+        .saved_views
+        .values()
+        .map(|saved| SavedViewInfo {
+            name: saved.name.clone(),
+            created_at: saved.created_at,
+            node_count: saved.view.node_set.len(),
+        })
+        .collect();
+    ans.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(ans)
+}
+
+pub async fn list_saved_views(GraphId(graph_id): GraphId) -> Json<ResponseStatus> {
+    process_resp(list_saved_views_inner(&graph_id))
+}
+
+/// 以给定名字保存当前视图, 同名视图会被覆盖
+#[auto_context::auto_context]
+fn save_view_inner(key: &ViewKey, query: SavedViewQuery) -> Result<()> {
+    let mut graphs = STATE.lock().unwrap();
+    let (_, view) = get_view_mut(&mut graphs, key)?;
+    let saved = SavedView {
+        name: query.name.clone(),
+        created_at: now_millis(),
+        view: view.clone(),
+    };
+    get_state_mut(&mut graphs, &key.graph_id)?
+        .saved_views
+        .insert(query.name, saved);
     Ok(())
 }
 
-pub async fn post_node(Json(query): Json<Node>) -> Json<ResponseStatus> {
-    process_resp(post_node_inner(query))
+pub async fn save_view(key: ViewKey, Query(query): Query<SavedViewQuery>) -> Json<ResponseStatus> {
+    process_resp(save_view_inner(&key, query))
 }
 
+/// 用保存的视图替换当前视图
 #[auto_context::auto_context]
-fn delete_node_inner(id: String) -> Result<()> {
-    {
-        let mut pre_state = STATE.lock().unwrap();
-        let state = pre_state
-            .as_mut()
-            .ok_or(anyhow::anyhow!("state not found"))?;
-        state.node_set.remove(&id);
-    }
-    clean_signal_inner()?;
+fn switch_view_inner(key: &ViewKey, query: SavedViewQuery) -> Result<()> {
+    let saved = get_saved_view(key, &query.name)?;
+    mutate_view(key, "switch_view", &query, |data, view| {
+        *view = saved.view;
+        view.prune(data);
+        Ok(())
+    })
+}
+
+pub async fn switch_view(
+    key: ViewKey,
+    Query(query): Query<SavedViewQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(switch_view_inner(&key, query))
+}
+
+#[auto_context::auto_context]
+fn delete_saved_view_inner(graph_id: &str, query: SavedViewQuery) -> Result<()> {
+    let mut graphs = STATE.lock().unwrap();
+    get_state_mut(&mut graphs, graph_id)?
+        .saved_views
+        .remove(&query.name)
+        .ok_or(anyhow::anyhow!("view {} not found", query.name))?;
     Ok(())
 }
 
-pub async fn delete_node(Query(query): Query<Node>) -> Json<ResponseStatus> {
-    process_resp(delete_node_inner(query.id))
+pub async fn delete_saved_view(
+    GraphId(graph_id): GraphId,
+    Query(query): Query<SavedViewQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(delete_saved_view_inner(&graph_id, query))
+}
+
+#[auto_context::auto_context]
+fn get_saved_view(key: &ViewKey, name: &str) -> Result<SavedView> {
+    get_state(&STATE.lock().unwrap(), &key.graph_id)?
+        .saved_views
+        .get(name)
+        .cloned()
+        .ok_or(anyhow::anyhow!("view {} not found", name))
+}
+
+/// 比较两个保存的视图, `to` 为空时与当前视图比较
+#[auto_context::auto_context]
+fn diff_views_inner(key: &ViewKey, query: ViewDiffQuery) -> Result<ViewDiff> {
+    let from = get_saved_view(key, &query.from)?.view;
+    let (data, to) = match &query.to {
+        Some(to) => {
+            let data = get_state(&STATE.lock().unwrap(), &key.graph_id)?
+                .data
+                .clone();
+            (data, get_saved_view(key, to)?.view)
+        }
+        None => {
+            let mut graphs = STATE.lock().unwrap();
+            let (data, view) = get_view_mut(&mut graphs, key)?;
+            (data.clone(), view.clone())
+        }
+    };
+    let to_node = |id: &String| Node {
+        id: id.clone(),
+        name: data.node_id_to_name.get(id).cloned(),
+    };
+    let mut added_nodes: Vec<Node> = to
+        .node_set
+        .difference(&from.node_set)
+        .map(to_node)
+        .collect();
+    let mut removed_nodes: Vec<Node> = from
+        .node_set
+        .difference(&to.node_set)
+        .map(to_node)
+        .collect();
+    added_nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    removed_nodes.sort_by(|a, b| a.id.cmp(&b.id));
+    let mut renamed = Vec::new();
+    let rename_ids: HashSet<&String> = from
+        .node_id_to_new_name
+        .keys()
+        .chain(to.node_id_to_new_name.keys())
+        .collect();
+    for id in rename_ids {
+        let from_name = from.node_id_to_new_name.get(id);
+        let to_name = to.node_id_to_new_name.get(id);
+        if from_name != to_name {
+            renamed.push(RenameDiff {
+                id: id.clone(),
+                from: from_name.cloned(),
+                to: to_name.cloned(),
+            });
+        }
+    }
+    renamed.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(ViewDiff {
+        added_nodes,
+        removed_nodes,
+        renamed,
+    })
+}
+
+pub async fn diff_views(key: ViewKey, Query(query): Query<ViewDiffQuery>) -> Json<ResponseStatus> {
+    process_resp(diff_views_inner(&key, query))
+}
+
+#[auto_context::auto_context]
+fn annotate_node_inner(key: &ViewKey, query: NodeAnnotation) -> Result<()> {
+    mutate_view(key, "annotate_node", &query, |_, view| {
+        match &query.annotation {
+            Some(annotation) => {
+                view.node_annotations
+                    .insert(query.id.clone(), annotation.clone());
+            }
+            None => {
+                view.node_annotations.remove(&query.id);
+            }
+        }
+        Ok(())
+    })
+}
+
+pub async fn annotate_node(
+    key: ViewKey,
+    Json(query): Json<NodeAnnotation>,
+) -> Json<ResponseStatus> {
+    process_resp(annotate_node_inner(&key, query))
+}
+
+/// 折叠或展开一个容器, collapsed 为空时切换
+#[auto_context::auto_context]
+fn collapse_node_inner(key: &ViewKey, query: CollapseQuery) -> Result<()> {
+    mutate_view(key, "collapse_node", &query, |data, view| {
+        if !data.node_parent.values().any(|father| *father == query.id) {
+            return Err(anyhow::anyhow!("node {} is not a container", query.id));
+        }
+        let collapsed = query
+            .collapsed
+            .unwrap_or(!view.collapsed.contains(&query.id));
+        if collapsed {
+            view.collapsed.insert(query.id.clone());
+        } else {
+            view.collapsed.remove(&query.id);
+        }
+        Ok(())
+    })
+}
+
+pub async fn collapse_node(
+    key: ViewKey,
+    Query(query): Query<CollapseQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(collapse_node_inner(&key, query))
+}
+
+/// 展开所有容器
+#[auto_context::auto_context]
+fn expand_all_inner(key: &ViewKey) -> Result<()> {
+    mutate_view(key, "expand_all", &(), |_, view| {
+        view.collapsed.clear();
+        Ok(())
+    })
+}
+
+pub async fn expand_all(key: ViewKey) -> Json<ResponseStatus> {
+    process_resp(expand_all_inner(&key))
 }
 
 #[auto_context::auto_context]
-fn put_node_inner(query: Node) -> Result<()> {
+fn delete_node_inner(key: &ViewKey, id: String) -> Result<()> {
+    mutate_view(key, "delete_node", &id, |data, view| {
+        view.node_set.remove(&id);
+        clean_signal_view(data, view)?;
+        Ok(())
+    })
+}
+
+pub async fn delete_node(key: ViewKey, Query(query): Query<Node>) -> Json<ResponseStatus> {
+    process_resp(delete_node_inner(&key, query.id))
+}
+
+#[auto_context::auto_context]
+fn put_node_inner(key: &ViewKey, query: Node) -> Result<()> {
     println!("put node {:?}", query);
-    let mut pre_state = STATE.lock().unwrap();
-    let state = pre_state
-        .as_mut()
-        .ok_or(anyhow::anyhow!("state not found"))?;
-    state.node_set.insert(query.id.clone());
-    Ok(())
+    mutate_view(key, "put_node", &query, |_, view| {
+        view.node_set.insert(query.id.clone());
+        Ok(())
+    })
 }
 
-pub async fn put_node_tree(Query(query): Query<Node>) -> Json<ResponseStatus> {
-    process_resp(put_node_tree_inner(query))
+pub async fn put_node_tree(key: ViewKey, Query(query): Query<TreeQuery>) -> Json<ResponseStatus> {
+    process_resp(put_node_tree_inner(&key, query))
 }
 
-pub async fn clean_signal() -> Json<ResponseStatus> {
-    process_resp(clean_signal_inner())
+pub async fn clean_signal(key: ViewKey) -> Json<ResponseStatus> {
+    process_resp(clean_signal_inner(&key))
 }
 
 #[auto_context::auto_context]
-fn clean_signal_inner() -> Result<()> {
-    let mut pre_state = STATE.lock().unwrap();
-    let state = pre_state
-        .as_mut()
-        .ok_or(anyhow::anyhow!("state not found"))?;
+fn clean_signal_inner(key: &ViewKey) -> Result<()> {
+    mutate_view(key, "clean_signal", &(), clean_signal_view)
+}
+
+/// 只保留与视图内其他节点有边相连的节点
+fn clean_signal_view(data: &GraphData, view: &mut ViewState) -> Result<()> {
     let mut now_node_set = HashSet::new();
-    for (from, to) in &state.edge_from_to {
-        if !state.node_set.contains(from) {
+    for (from, to) in &data.edge_from_to {
+        if !view.node_set.contains(from) {
             continue;
         }
         for tto in to {
-            if !state.node_set.contains(tto) {
+            if !view.node_set.contains(tto) {
                 continue;
             }
             now_node_set.insert(from.clone());
             now_node_set.insert(tto.clone());
         }
     }
-    state.node_set = now_node_set;
+    view.node_set = now_node_set;
 
     Ok(())
 }
 
-pub async fn filter_node_father_tree(Query(query): Query<Node>) -> Json<ResponseStatus> {
-    process_resp(filter_node_father_tree_inner(query))
+pub async fn filter_node_father_tree(
+    key: ViewKey,
+    Query(query): Query<TreeQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(filter_node_father_tree_inner(&key, query))
 }
 
 #[auto_context::auto_context]
-fn filter_node_father_tree_inner(query: Node) -> Result<()> {
-    let mut pre_state = STATE.lock().unwrap();
-    let state = pre_state
-        .as_mut()
-        .ok_or(anyhow::anyhow!("state not found"))?;
-    let is_init = state.node_set.len() <= 1;
-    let mut new_node_set = HashSet::new();
-    let mut now_deque = VecDeque::new();
-    now_deque.push_back(query.id.clone());
-    while let Some(now_id) = now_deque.pop_front() {
-        if new_node_set.contains(&now_id) {
-            continue;
-        }
-        new_node_set.insert(now_id.clone());
-        if let Some(fathers) = state.edge_to_from.get(&now_id) {
-            for father in fathers {
-                if state.node_set.contains(father) || is_init {
-                    now_deque.push_back(father.clone());
+fn filter_node_father_tree_inner(key: &ViewKey, query: TreeQuery) -> Result<()> {
+    mutate_view(key, "filter_node_father_tree", &query, |data, view| {
+        let is_init = view.node_set.len() <= 1;
+        let mut new_node_set = HashSet::new();
+        let mut now_deque = VecDeque::new();
+        now_deque.push_back((query.id.clone(), 0));
+        while let Some((now_id, d)) = now_deque.pop_front() {
+            if new_node_set.contains(&now_id) {
+                continue;
+            }
+            new_node_set.insert(now_id.clone());
+            if query.depth.map(|depth| d >= depth).unwrap_or(false) {
+                continue;
+            }
+            if let Some(fathers) = data.edge_to_from.get(&now_id) {
+                for father in fathers {
+                    if view.node_set.contains(father) || is_init {
+                        now_deque.push_back((father.clone(), d + 1));
+                    }
                 }
             }
         }
-    }
-    state.node_set = new_node_set;
+        view.node_set = new_node_set;
 
-    Ok(())
+        Ok(())
+    })
 }
 
 #[auto_context::auto_context]
-fn put_node_tree_inner(query: Node) -> Result<()> {
+fn put_node_tree_inner(key: &ViewKey, query: TreeQuery) -> Result<()> {
     println!("put tree node {:?}", query);
-    let mut pre_state = STATE.lock().unwrap();
-    let state = pre_state
-        .as_mut()
-        .ok_or(anyhow::anyhow!("state not found"))?;
-    let is_init = state.node_set.len() <= 1;
-    let mut now_deque = VecDeque::new();
-    let mut new_node_set = HashSet::new();
-    now_deque.push_back(query.id);
-    while let Some(now_id) = now_deque.pop_front() {
-        if new_node_set.contains(&now_id) {
-            continue;
-        }
-        new_node_set.insert(now_id.clone());
-        if let Some(tos) = state.edge_from_to.get(&now_id) {
-            for to in tos {
-                if is_init || state.node_set.contains(to) {
-                    now_deque.push_back(to.clone());
+    mutate_view(key, "put_node_tree", &query, |data, view| {
+        let is_init = view.node_set.len() <= 1;
+        let mut now_deque = VecDeque::new();
+        let mut new_node_set = HashSet::new();
+        now_deque.push_back((query.id.clone(), 0));
+        while let Some((now_id, d)) = now_deque.pop_front() {
+            if new_node_set.contains(&now_id) {
+                continue;
+            }
+            new_node_set.insert(now_id.clone());
+            if query.depth.map(|depth| d >= depth).unwrap_or(false) {
+                continue;
+            }
+            if let Some(tos) = data.edge_from_to.get(&now_id) {
+                for to in tos {
+                    if is_init || view.node_set.contains(to) {
+                        now_deque.push_back((to.clone(), d + 1));
+                    }
                 }
             }
         }
-    }
-    state.node_set = new_node_set;
-    Ok(())
+        view.node_set = new_node_set;
+        Ok(())
+    })
+}
+
+/// 把节点 depth 层以内的调用者/被调用者加入当前视图, 不移除已有节点
+#[auto_context::auto_context]
+fn expand_node_inner(key: &ViewKey, query: ExpandQuery) -> Result<usize> {
+    mutate_view(key, "expand", &query, |data, view| {
+        let reached = analysis::neighbourhood(
+            data,
+            std::slice::from_ref(&query.id),
+            Some(query.depth.unwrap_or(1)),
+            query.direction.unwrap_or_default(),
+        );
+        let before = view.node_set.len();
+        view.node_set.extend(reached.into_keys());
+        Ok(view.node_set.len() - before)
+    })
 }
 
-pub async fn put_node(Json(query): Json<Node>) -> Json<ResponseStatus> {
-    process_resp(put_node_inner(query))
+pub async fn expand_node(key: ViewKey, Query(query): Query<ExpandQuery>) -> Json<ResponseStatus> {
+    process_resp(expand_node_inner(&key, query))
 }
 
+pub async fn put_node(key: ViewKey, Json(query): Json<Node>) -> Json<ResponseStatus> {
+    process_resp(put_node_inner(&key, query))
+}
+
+/// 列出所有存在递归的强连通分量
 #[auto_context::auto_context]
-fn search_node_inner(query: SearchNodeQuery) -> Result<Vec<Node>> {
-    println!("search node {:?}", query);
-    let pre_state = STATE.lock().unwrap();
-    let state = pre_state
-        .as_ref()
-        .ok_or(anyhow::anyhow!("state not found"))?;
-    let edge_filter: Option<HashSet<String>> = query.related_node_id.map(|id| {
-        state
-            .edge_from_to
-            .get(&id)
-            .map(|v| v.clone())
+fn list_cycles_inner(graph_id: &str) -> Result<Vec<Cycle>> {
+    let data = get_state(&STATE.lock().unwrap(), graph_id)?.data.clone();
+    Ok(analysis::strongly_connected_components(&data)
+        .into_iter()
+        .map(|component| Cycle {
+            nodes: component
+                .into_iter()
+                .map(|id| Node {
+                    name: data.node_id_to_name.get(&id).cloned(),
+                    id,
+                })
+                .collect(),
+        })
+        .collect())
+}
+
+pub async fn list_cycles(GraphId(graph_id): GraphId) -> Json<ResponseStatus> {
+    process_resp(list_cycles_inner(&graph_id))
+}
+
+/// 按 id 或名字通配找出入口函数, 统计从入口不可达的函数并按文件分组
+#[auto_context::auto_context]
+fn dead_code_inner(graph_id: &str, query: &DeadCodeQuery) -> Result<DeadCodeReport> {
+    let data = get_state(&STATE.lock().unwrap(), graph_id)?.data.clone();
+    let split = |v: &Option<String>| -> Vec<String> {
+        v.as_deref()
             .unwrap_or_default()
+            .split(',')
+            .map(|v| v.trim().to_owned())
+            .filter(|v| !v.is_empty())
+            .collect()
+    };
+    let patterns = split(&query.pattern);
+    let mut entries: Vec<String> = split(&query.entry)
+        .into_iter()
+        .filter(|id| data.node_id_to_name.contains_key(id))
+        .collect();
+    for id in analysis::function_ids(&data) {
+        let name = analysis::short_name(&data.node_id_to_name[id]);
+        if patterns
+            .iter()
+            .any(|pattern| analysis::wildcard_match(pattern, name))
+        {
+            entries.push(id.clone());
+        }
+    }
+    entries.sort();
+    entries.dedup();
+    if entries.is_empty() {
+        return Err(anyhow::anyhow!("no entry point matched"));
+    }
+    let to_node = |id: &String| Node {
+        id: id.clone(),
+        name: data.node_id_to_name.get(id).cloned(),
+    };
+    let unreachable = analysis::unreachable_functions(&data, &entries);
+    let mut files: Vec<DeadCodeFile> = Vec::new();
+    for id in &unreachable {
+        let file = analysis::display_name(&data, analysis::file_of(&data, id));
+        match files.iter_mut().find(|v| v.file == file) {
+            Some(v) => v.nodes.push(to_node(id)),
+            None => files.push(DeadCodeFile {
+                file,
+                nodes: vec![to_node(id)],
+            }),
+        }
+    }
+    files.sort_by(|a, b| a.file.cmp(&b.file));
+    Ok(DeadCodeReport {
+        entry_points: entries.iter().map(to_node).collect(),
+        total_functions: analysis::function_ids(&data).len(),
+        unreachable_count: unreachable.len(),
+        files,
+    })
+}
+
+pub async fn dead_code(GraphId(graph_id): GraphId, Query(query): Query<DeadCodeQuery>) -> Response {
+    let report = dead_code_inner(&graph_id, &query);
+    let (content, content_type, file_name) = match (query.format.unwrap_or_default(), &report) {
+        (ReportFormat::Markdown, Ok(report)) => (
+            report::dead_code_markdown(report),
+            "text/markdown; charset=utf-8",
+            "dead_code.md",
+        ),
+        (ReportFormat::Csv, Ok(report)) => (
+            report::dead_code_csv(report),
+            "text/csv; charset=utf-8",
+            "dead_code.csv",
+        ),
+        _ => return process_resp(report).into_response(),
+    };
+    download_response(content, content_type, file_name)
+}
+
+/// 以附件形式返回文本内容, 浏览器会直接下载
+fn download_response(content: String, content_type: &str, file_name: &str) -> Response {
+    (
+        [
+            (header::CONTENT_TYPE, content_type.to_owned()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", file_name),
+            ),
+        ],
+        content,
+    )
+        .into_response()
+}
+
+#[auto_context::auto_context]
+fn node_metrics_inner(graph_id: &str, query: MetricsQuery) -> Result<Vec<NodeMetrics>> {
+    let data = get_state(&STATE.lock().unwrap(), graph_id)?.data.clone();
+    let kind = query.sort_by.unwrap_or_default();
+    let mut ans: Vec<NodeMetrics> = analysis::metrics(&data).values().cloned().collect();
+    ans.sort_by(|a, b| {
+        analysis::metric_value(b, kind)
+            .total_cmp(&analysis::metric_value(a, kind))
+            .then_with(|| a.id.cmp(&b.id))
     });
+    if query.asc.unwrap_or(false) {
+        ans.reverse();
+    }
+    ans.truncate(query.limit.unwrap_or(DEFAULT_METRICS_LIMIT));
+    Ok(ans)
+}
+
+pub async fn node_metrics(
+    GraphId(graph_id): GraphId,
+    Query(query): Query<MetricsQuery>,
+) -> Json<ResponseStatus> {
+    process_blocking(move || node_metrics_inner(&graph_id, query)).await
+}
+
+/// 从修改过的函数出发沿调用者方向 BFS, 按距离排列所有可能受影响的函数
+#[auto_context::auto_context]
+fn impact_inner(key: &ViewKey, query: ImpactQuery) -> Result<ImpactResult> {
+    let data = get_state(&STATE.lock().unwrap(), &key.graph_id)?
+        .data
+        .clone();
+    let mut seeds = Vec::new();
+    let mut unresolved = Vec::new();
+    if let Some(diff) = &query.diff {
+        let (changed, _) = diff_functions(&data, diff)?;
+        seeds.extend(changed);
+    }
+    for changed in &query.changed {
+        let ids = analysis::resolve_nodes(&data, changed);
+        if ids.is_empty() {
+            unresolved.push(changed.clone());
+        }
+        seeds.extend(ids);
+    }
+    seeds.sort();
+    seeds.dedup();
+    let dist = analysis::neighbourhood(&data, &seeds, query.max_depth, Direction::Callers);
+    let mut impacted: Vec<ImpactNode> = dist
+        .iter()
+        .map(|(id, distance)| ImpactNode {
+            id: id.clone(),
+            name: data.node_id_to_name.get(id).cloned(),
+            distance: *distance,
+        })
+        .collect();
+    impacted.sort_by(|a, b| a.distance.cmp(&b.distance).then_with(|| a.id.cmp(&b.id)));
+    if query.apply.unwrap_or(false) {
+        mutate_view(key, "impact", &query, |_, view| {
+            view.node_set = dist.into_keys().collect();
+            Ok(())
+        })?;
+    }
+    Ok(ImpactResult {
+        seeds: seeds
+            .into_iter()
+            .map(|id| Node {
+                name: data.node_id_to_name.get(&id).cloned(),
+                id,
+            })
+            .collect(),
+        unresolved,
+        impacted,
+    })
+}
+
+/// 读取 diff 并找出修改行所在的函数, 返回 (函数 id, 图中找不到的文件)
+#[auto_context::auto_context]
+fn diff_functions(data: &GraphData, source: &DiffSource) -> Result<(Vec<String>, Vec<String>)> {
+    let diff = match (&source.diff, &source.diff_path, &source.repo) {
+        (Some(diff), _, _) => diff.clone(),
+        (None, Some(diff_path), _) => {
+            let path = resolve_within(&get_config().data_dir, diff_path)?;
+            std::fs::read_to_string(path)?
+        }
+        (None, None, Some(repo)) => {
+            let source_root = get_config()
+                .source_root
+                .ok_or(anyhow::anyhow!("source_root is not configured"))?;
+            let repo = resolve_within(&source_root, repo)?;
+            git_diff(
+                &repo.to_string_lossy(),
+                source.base.as_deref().unwrap_or("HEAD"),
+            )?
+        }
+        (None, None, None) => return Err(anyhow::anyhow!("diff, diff_path or repo is required")),
+    };
+    let changes = parse_unified_diff(&diff);
+    Ok(analysis::changed_functions(data, &changes))
+}
+
+#[auto_context::auto_context]
+fn diff_nodes_inner(graph_id: &str, source: DiffSource) -> Result<DiffNodesResult> {
+    let data = get_state(&STATE.lock().unwrap(), graph_id)?.data.clone();
+    let (changed, unmatched_files) = diff_functions(&data, &source)?;
+    Ok(DiffNodesResult {
+        changed: changed
+            .into_iter()
+            .map(|id| Node {
+                name: data.node_id_to_name.get(&id).cloned(),
+                id,
+            })
+            .collect(),
+        unmatched_files,
+    })
+}
+
+pub async fn diff_nodes(
+    GraphId(graph_id): GraphId,
+    Json(source): Json<DiffSource>,
+) -> Json<ResponseStatus> {
+    process_resp(diff_nodes_inner(&graph_id, source))
+}
+
+pub async fn impact(key: ViewKey, Json(query): Json<ImpactQuery>) -> Json<ResponseStatus> {
+    process_resp(impact_inner(&key, query))
+}
+
+/// 撤销最近一次操作, 返回被撤销的操作
+#[auto_context::auto_context]
+fn undo_inner(key: &ViewKey) -> Result<Operation> {
+    let mut graphs = STATE.lock().unwrap();
+    let state = get_state_mut(&mut graphs, &key.graph_id)?;
+    let session_view = state.views.entry(key.session_id.clone()).or_default();
+    let mut op = session_view
+        .history
+        .applied
+        .pop()
+        .ok_or(anyhow::anyhow!("nothing to undo"))?;
+    std::mem::swap(&mut op.snapshot, &mut session_view.view);
+    session_view.history.undone.push(op.clone());
+    Ok(op)
+}
+
+pub async fn undo(key: ViewKey) -> Json<ResponseStatus> {
+    process_resp(undo_inner(&key))
+}
+
+/// 重做最近一次被撤销的操作
+#[auto_context::auto_context]
+fn redo_inner(key: &ViewKey) -> Result<Operation> {
+    let mut graphs = STATE.lock().unwrap();
+    let state = get_state_mut(&mut graphs, &key.graph_id)?;
+    let session_view = state.views.entry(key.session_id.clone()).or_default();
+    let mut op = session_view
+        .history
+        .undone
+        .pop()
+        .ok_or(anyhow::anyhow!("nothing to redo"))?;
+    std::mem::swap(&mut op.snapshot, &mut session_view.view);
+    session_view.history.applied.push(op.clone());
+    Ok(op)
+}
+
+pub async fn redo(key: ViewKey) -> Json<ResponseStatus> {
+    process_resp(redo_inner(&key))
+}
+
+#[auto_context::auto_context]
+fn history_inner(key: &ViewKey) -> Result<ViewHistory> {
+    let mut graphs = STATE.lock().unwrap();
+    let state = get_state_mut(&mut graphs, &key.graph_id)?;
+    let session_view = state.views.entry(key.session_id.clone()).or_default();
+    Ok(session_view.history.clone())
+}
+
+pub async fn history(key: ViewKey) -> Json<ResponseStatus> {
+    process_resp(history_inner(&key))
+}
+
+/// 计算 from 到 to 的最短调用路径, apply 时用路径替换当前视图
+#[auto_context::auto_context]
+fn shortest_path_inner(key: &ViewKey, query: PathQuery, apply: bool) -> Result<Vec<Node>> {
+    let (data, view) = {
+        let mut graphs = STATE.lock().unwrap();
+        let (data, view) = get_view_mut(&mut graphs, key)?;
+        (data.clone(), view.clone())
+    };
+    let within = query.within_view.unwrap_or(false).then_some(&view.node_set);
+    let path = analysis::shortest_path(&data, &query.from, &query.to, within).ok_or(
+        anyhow::anyhow!("no path from {} to {}", query.from, query.to),
+    )?;
+    if apply {
+        mutate_view(key, "shortest_path", &query, |_, view| {
+            view.node_set = path.iter().cloned().collect();
+            Ok(())
+        })?;
+    }
+    Ok(path
+        .into_iter()
+        .map(|id| Node {
+            name: data.node_id_to_name.get(&id).cloned(),
+            id,
+        })
+        .collect())
+}
+
+pub async fn shortest_path(key: ViewKey, Query(query): Query<PathQuery>) -> Json<ResponseStatus> {
+    process_resp(shortest_path_inner(&key, query, false))
+}
+
+pub async fn put_shortest_path(
+    key: ViewKey,
+    Query(query): Query<PathQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(shortest_path_inner(&key, query, true))
+}
+
+/// 枚举 from 到 to 的所有简单路径, apply 时把路径上的节点并入当前视图
+#[auto_context::auto_context]
+fn all_paths_inner(key: &ViewKey, query: AllPathsQuery, apply: bool) -> Result<AllPathsResult> {
+    let (data, view) = {
+        let mut graphs = STATE.lock().unwrap();
+        let (data, view) = get_view_mut(&mut graphs, key)?;
+        (data.clone(), view.clone())
+    };
+    let within = query.within_view.unwrap_or(false).then_some(&view.node_set);
+    let (paths, truncated) = analysis::all_simple_paths(
+        &data,
+        &query.from,
+        &query.to,
+        query.max_depth.unwrap_or(DEFAULT_MAX_DEPTH),
+        query.max_paths.unwrap_or(DEFAULT_MAX_PATHS),
+        within,
+    );
+    if apply {
+        mutate_view(key, "paths", &query, |_, view| {
+            view.node_set.extend(paths.iter().flatten().cloned());
+            Ok(())
+        })?;
+    }
+    let paths = paths
+        .into_iter()
+        .map(|path| {
+            path.into_iter()
+                .map(|id| Node {
+                    name: data.node_id_to_name.get(&id).cloned(),
+                    id,
+                })
+                .collect()
+        })
+        .collect();
+    Ok(AllPathsResult { paths, truncated })
+}
+
+pub async fn all_paths(key: ViewKey, Query(query): Query<AllPathsQuery>) -> Json<ResponseStatus> {
+    process_resp(all_paths_inner(&key, query, false))
+}
+
+pub async fn put_all_paths(
+    key: ViewKey,
+    Query(query): Query<AllPathsQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(all_paths_inner(&key, query, true))
+}
+
+#[auto_context::auto_context]
+fn search_node_inner(key: &ViewKey, query: SearchNodeQuery) -> Result<Vec<NodeInfo>> {
+    println!("search node {:?}", query);
+    let data = get_state(&STATE.lock().unwrap(), &key.graph_id)?
+        .data
+        .clone();
+    let config = get_config();
+    let template = session_editor_template(&config, &key.session_id);
+    let edge_filter: Option<HashSet<String>> = query
+        .related_node_id
+        .map(|id| data.edge_from_to.get(&id).cloned().unwrap_or_default());
     let mut ans = Vec::new();
-    for (id, name) in state.node_id_to_name.iter() {
+    for (id, name) in data.node_id_to_name.iter() {
         if let Some(hint_node_id) = &query.hint_node_id {
             if !name.contains(hint_node_id) {
                 continue;
@@ -243,11 +1110,24 @@ fn search_node_inner(query: SearchNodeQuery) -> Result<Vec<Node>> {
                 continue;
             }
         }
-        ans.push(Node {
+        let meta = data.node_meta.get(id);
+        ans.push(NodeInfo {
             id: id.clone(),
             name: Some(name.clone()),
+            meta: meta.cloned().unwrap_or_default(),
+            open_url: node_open_url(&config, template.as_deref(), meta),
         });
     }
+    if let Some(kind) = query.order_by {
+        let metrics = analysis::metrics(&data);
+        let value = |id: &String| {
+            metrics
+                .get(id)
+                .map(|m| analysis::metric_value(m, kind))
+                .unwrap_or_default()
+        };
+        ans.sort_by(|a, b| value(&b.id).total_cmp(&value(&a.id)));
+    }
     // 只保留前100个
     ans.truncate(100);
 
@@ -256,21 +1136,428 @@ fn search_node_inner(query: SearchNodeQuery) -> Result<Vec<Node>> {
     Ok(ans)
 }
 
-pub async fn search_node(Query(query): Query<SearchNodeQuery>) -> Json<ResponseStatus> {
-    process_resp(search_node_inner(query))
+pub async fn search_node(
+    key: ViewKey,
+    Query(query): Query<SearchNodeQuery>,
+) -> Json<ResponseStatus> {
+    process_blocking(move || search_node_inner(&key, query)).await
+}
+
+#[auto_context::auto_context]
+fn node_detail_inner(key: &ViewKey, id: &str) -> Result<NodeDetail> {
+    let mut graphs = STATE.lock().unwrap();
+    let (data, view) = get_view_mut(&mut graphs, key)?;
+    let name = data
+        .node_id_to_name
+        .get(id)
+        .ok_or(anyhow::anyhow!("node {} not found", id))?;
+    let sorted = |ids: Option<&HashSet<String>>| {
+        let mut ids = ids.into_iter().flatten().cloned().collect::<Vec<_>>();
+        ids.sort();
+        ids
+    };
+    Ok(NodeDetail {
+        id: id.to_owned(),
+        name: name.clone(),
+        new_name: view.node_id_to_new_name.get(id).cloned(),
+        annotation: view.node_annotations.get(id).cloned(),
+        in_view: view.node_set.contains(id),
+        parent: data.node_parent.get(id).cloned(),
+        meta: data.node_meta.get(id).cloned().unwrap_or_default(),
+        callers: sorted(data.edge_to_from.get(id)),
+        callees: sorted(data.edge_from_to.get(id)),
+    })
+}
+
+pub async fn node_detail(
+    key: ViewKey,
+    Path(params): Path<HashMap<String, String>>,
+) -> Json<ResponseStatus> {
+    let id = params.get("id").cloned().unwrap_or_default();
+    process_resp(node_detail_inner(&key, &id))
+}
+
+/// 按节点位置从 source_root 中读取函数源码, 前后附带 context 行
+#[auto_context::auto_context]
+fn node_source_inner(graph_id: &str, id: &str, query: SourceQuery) -> Result<NodeSource> {
+    let source_root = get_config()
+        .source_root
+        .ok_or(anyhow::anyhow!("source_root is not configured"))?;
+    let meta = get_state(&STATE.lock().unwrap(), graph_id)?
+        .data
+        .node_meta
+        .get(id)
+        .cloned()
+        .ok_or(anyhow::anyhow!("node {} not found", id))?;
+    let line = meta
+        .line
+        .ok_or(anyhow::anyhow!("node {} has no source location", id))?;
+    let path = resolve_within(&source_root, &meta.file)?;
+    let raw = std::fs::read_to_string(path)?;
+    let lines = raw.lines().collect::<Vec<_>>();
+    if line > lines.len() {
+        return Err(anyhow::anyhow!(
+            "line {} is out of range of {}",
+            line,
+            meta.file
+        ));
+    }
+    let node_end_line = meta
+        .end_line
+        .unwrap_or(line + MAX_SOURCE_LINES - 1)
+        .clamp(line, lines.len());
+    let context = query
+        .context
+        .unwrap_or(DEFAULT_CONTEXT_LINES)
+        .min(MAX_CONTEXT_LINES);
+    let start_line = line.saturating_sub(context).max(1);
+    let end_line = (node_end_line + context).min(lines.len());
+    Ok(NodeSource {
+        id: id.to_owned(),
+        file: meta.file,
+        start_line,
+        end_line,
+        line,
+        node_end_line,
+        source: lines[start_line - 1..end_line].join("\n"),
+    })
+}
+
+pub async fn node_source(
+    GraphId(graph_id): GraphId,
+    Path(params): Path<HashMap<String, String>>,
+    Query(query): Query<SourceQuery>,
+) -> Json<ResponseStatus> {
+    let id = params.get("id").cloned().unwrap_or_default();
+    process_resp(node_source_inner(&graph_id, &id, query))
+}
+
+#[auto_context::auto_context]
+fn init_graph_inner(graph_id: &str, query: InitGraphQuery) -> Result<()> {
+    let config = get_config();
+    let source = match query.path {
+        Some(path) => resolve_within(&config.data_dir, &path)?
+            .to_string_lossy()
+            .to_string(),
+        None => {
+            let has_init = STATE.lock().unwrap().contains_key(graph_id);
+            if has_init {
+                println!("graph {} already initialized", graph_id);
+                return Ok(());
+            }
+            config.dot_path
+        }
+    };
+    let data = load_graph(&source)?;
+    let mut graphs = STATE.lock().unwrap();
+    match graphs.get_mut(graph_id) {
+        Some(state) => replace_graph_data(state, data),
+        None => {
+            graphs.insert(
+                graph_id.to_owned(),
+                AppState {
+                    data: Arc::new(data),
+                    views: HashMap::new(),
+                    saved_views: HashMap::new(),
+                },
+            );
+        }
+    }
+    println!("init graph {} done {}", graph_id, source);
+    Ok(())
+}
+
+/// 保存当前视图到工作区文件, 返回文件路径
+#[auto_context::auto_context]
+fn save_workspace_inner(key: &ViewKey, query: WorkspaceQuery) -> Result<String> {
+    let workspace = {
+        let mut graphs = STATE.lock().unwrap();
+        let (data, view) = get_view_mut(&mut graphs, key)?;
+        Workspace {
+            source: data.source.clone(),
+            source_hash: data.source_hash.clone(),
+            saved_at: now_millis(),
+            view: view.clone(),
+        }
+    };
+    let path = save_workspace(&query.name, &workspace)?;
+    println!("save workspace {} done {}", query.name, path);
+    Ok(path)
 }
 
+pub async fn save_workspace_handler(
+    key: ViewKey,
+    Query(query): Query<WorkspaceQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(save_workspace_inner(&key, query))
+}
+
+/// 从工作区文件恢复视图, 图未加载时按工作区记录的源文件加载.
+/// 源文件内容与保存时不一致时拒绝加载, 除非指定 force
 #[auto_context::auto_context]
-fn init_graph_inner() -> Result<()> {
-    let has_init = STATE.lock().unwrap().is_some();
-    if has_init {
-        println!("graph already initialized");
-        return Ok(());
+fn load_workspace_inner(key: &ViewKey, query: WorkspaceQuery) -> Result<WorkspaceLoadResult> {
+    let workspace = read_workspace(&query.name)?;
+    let has_init = STATE.lock().unwrap().contains_key(&key.graph_id);
+    if !has_init {
+        let source = check_graph_source(&workspace.source)?;
+        let data = load_graph(&source)?;
+        STATE
+            .lock()
+            .unwrap()
+            .entry(key.graph_id.clone())
+            .or_insert_with(|| AppState {
+                data: Arc::new(data),
+                views: HashMap::new(),
+                saved_views: HashMap::new(),
+            });
+    }
+    let loaded = get_state(&STATE.lock().unwrap(), &key.graph_id)?
+        .data
+        .clone();
+    if loaded.source != workspace.source {
+        return Err(anyhow::anyhow!(
+            "graph {} is loaded from {}, workspace was saved from {}",
+            key.graph_id,
+            loaded.source,
+            workspace.source
+        ));
     }
-    let cg = parse_from_dot("data/master.dot")?;
+    let hash_matched = loaded.source_hash == workspace.source_hash;
+    if !hash_matched && !query.force.unwrap_or(false) {
+        return Err(anyhow::anyhow!(
+            "{} changed since workspace {} was saved, load with force=true to apply anyway",
+            workspace.source,
+            query.name
+        ));
+    }
+    let dropped_nodes = mutate_view(key, "load_workspace", &query, |data, view| {
+        let mut new_view = workspace.view.clone();
+        new_view.prune(data);
+        let dropped_nodes = workspace.view.node_set.len() - new_view.node_set.len();
+        *view = new_view;
+        Ok(dropped_nodes)
+    })?;
+    Ok(WorkspaceLoadResult {
+        source: workspace.source,
+        hash_matched,
+        dropped_nodes,
+        warning: (!hash_matched).then(|| "dot file changed since workspace was saved".to_owned()),
+    })
+}
+
+pub async fn load_workspace_handler(
+    key: ViewKey,
+    Query(query): Query<WorkspaceQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(load_workspace_inner(&key, query))
+}
+
+/// 工作区等文件中记录的图源只能是默认 dot 文件或 data_dir 下的文件
+#[auto_context::auto_context]
+fn check_graph_source(source: &str) -> Result<String> {
+    let config = get_config();
+    let default_source = std::path::Path::new(&config.dot_path).canonicalize().ok();
+    let canonical = std::path::Path::new(source).canonicalize()?;
+    if Some(&canonical) != default_source.as_ref() {
+        resolve_within(&config.data_dir, &canonical.to_string_lossy())?;
+    }
+    Ok(source.to_owned())
+}
+
+/// 替换图数据, 各会话视图、操作历史和保存的视图中只保留仍然存在的节点
+fn replace_graph_data(state: &mut AppState, data: GraphData) {
+    for SessionView { view, history } in state.views.values_mut() {
+        view.prune(&data);
+        for op in history.applied.iter_mut().chain(history.undone.iter_mut()) {
+            op.snapshot.prune(&data);
+        }
+    }
+    for saved in state.saved_views.values_mut() {
+        saved.view.prune(&data);
+    }
+    state.data = Arc::new(data);
+}
+
+/// 重新解析图的源文件, 保留当前视图中仍然存在的节点和改名
+#[auto_context::auto_context]
+fn reload_graph_inner(graph_id: &str) -> Result<()> {
+    let source = get_state(&STATE.lock().unwrap(), graph_id)?
+        .data
+        .source
+        .clone();
+    let data = load_graph(&source)?;
+    let mut graphs = STATE.lock().unwrap();
+    replace_graph_data(get_state_mut(&mut graphs, graph_id)?, data);
+    println!("reload graph {} done {}", graph_id, source);
+    Ok(())
+}
+
+pub async fn reload_graph(GraphId(graph_id): GraphId) -> Json<ResponseStatus> {
+    process_resp(reload_graph_inner(&graph_id))
+}
+
+#[auto_context::auto_context]
+fn unload_graph_inner(graph_id: &str) -> Result<()> {
+    STATE
+        .lock()
+        .unwrap()
+        .remove(graph_id)
+        .ok_or(anyhow::anyhow!("graph {} not found", graph_id))?;
+    Ok(())
+}
+
+pub async fn unload_graph(GraphId(graph_id): GraphId) -> Json<ResponseStatus> {
+    process_resp(unload_graph_inner(&graph_id))
+}
+
+fn create_session_inner() -> Result<SessionInfo> {
+    let session = SessionInfo {
+        session_id: format!("{:032x}", rand::random::<u128>()),
+        created_at: now_millis(),
+        editor: None,
+    };
+    SESSIONS
+        .lock()
+        .unwrap()
+        .insert(session.session_id.clone(), session.clone());
+    Ok(session)
+}
+
+pub async fn create_session() -> (
+    AppendHeaders<[(HeaderName, String); 1]>,
+    Json<ResponseStatus>,
+) {
+    let resp = create_session_inner();
+    let cookie = resp
+        .as_ref()
+        .map(|session| format!("{}={}; Path=/", SESSION_COOKIE, session.session_id))
+        .unwrap_or_default();
+    (
+        AppendHeaders([(header::SET_COOKIE, cookie)]),
+        process_resp(resp),
+    )
+}
+
+#[auto_context::auto_context]
+fn get_session_inner(key: &ViewKey) -> Result<SessionInfo> {
+    let session = SESSIONS.lock().unwrap().get(&key.session_id).cloned();
+    match session {
+        Some(session) => Ok(session),
+        None if key.session_id == DEFAULT_SESSION => Ok(SessionInfo {
+            session_id: DEFAULT_SESSION.to_owned(),
+            ..Default::default()
+        }),
+        None => Err(anyhow::anyhow!("session {} not found", key.session_id)),
+    }
+}
+
+pub async fn get_session(key: ViewKey) -> Json<ResponseStatus> {
+    process_resp(get_session_inner(&key))
+}
+
+/// 设置会话使用的编辑器, 默认会话的设置也保存在 SESSIONS 中
+#[auto_context::auto_context]
+fn set_session_editor_inner(key: &ViewKey, query: EditorQuery) -> Result<SessionInfo> {
+    if let Some(editor) = &query.editor {
+        get_config().editor_template(editor)?;
+    }
+    let mut sessions = SESSIONS.lock().unwrap();
+    if key.session_id == DEFAULT_SESSION {
+        sessions
+            .entry(DEFAULT_SESSION.to_owned())
+            .or_insert_with(|| SessionInfo {
+                session_id: DEFAULT_SESSION.to_owned(),
+                ..Default::default()
+            });
+    }
+    let session = sessions
+        .get_mut(&key.session_id)
+        .ok_or(anyhow::anyhow!("session {} not found", key.session_id))?;
+    session.editor = query.editor;
+    Ok(session.clone())
+}
+
+pub async fn set_session_editor(
+    key: ViewKey,
+    Json(query): Json<EditorQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(set_session_editor_inner(&key, query))
+}
+
+/// 会话选择的编辑器模板, 未选择时使用 default_editor
+fn session_editor_template(config: &Config, session_id: &str) -> Option<String> {
+    let editor = SESSIONS
+        .lock()
+        .unwrap()
+        .get(session_id)
+        .and_then(|session| session.editor.clone())
+        .or_else(|| config.default_editor.clone())?;
+    config.editor_template(&editor).ok()
+}
+
+fn node_open_url(
+    config: &Config,
+    template: Option<&str>,
+    meta: Option<&NodeMeta>,
+) -> Option<String> {
+    let meta = meta?;
+    Some(config.open_url(template?, &meta.file, meta.line?, meta.column.unwrap_or(1)))
+}
+
+/// 删除会话及其在所有图上的视图
+#[auto_context::auto_context]
+fn delete_session_inner(key: &ViewKey) -> Result<()> {
+    if key.session_id == DEFAULT_SESSION {
+        return Err(anyhow::anyhow!("default session can not be deleted"));
+    }
+    for state in STATE.lock().unwrap().values_mut() {
+        state.views.remove(&key.session_id);
+    }
+    SESSIONS.lock().unwrap().remove(&key.session_id);
+    Ok(())
+}
+
+pub async fn delete_session(key: ViewKey) -> Json<ResponseStatus> {
+    process_resp(delete_session_inner(&key))
+}
+
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+fn list_graphs_inner() -> Result<Vec<GraphInfo>> {
+    let graphs = STATE.lock().unwrap();
+    let mut ans: Vec<GraphInfo> = graphs
+        .iter()
+        .map(|(id, state)| GraphInfo {
+            id: id.clone(),
+            source: state.data.source.clone(),
+            node_count: state.data.node_id_to_name.len(),
+            edge_count: state.data.graph.edges.len(),
+            view_count: state.views.len(),
+        })
+        .collect();
+    ans.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(ans)
+}
+
+pub async fn list_graphs() -> Json<ResponseStatus> {
+    process_resp(list_graphs_inner())
+}
+
+#[auto_context::auto_context]
+pub fn load_graph(source: &str) -> Result<GraphData> {
+    let cg = parse_from_dot(source)?;
     let mut node_id_to_name = HashMap::new();
     let mut father_name = Vec::new();
     gen_node_id_to_name(&cg.nodes, &mut node_id_to_name, &mut father_name);
+    let mut node_parent = HashMap::new();
+    gen_node_parent(&cg.nodes, None, &mut node_parent);
+    let mut node_meta = HashMap::new();
+    gen_node_meta(&cg.nodes, &mut node_meta);
     let mut edge_from_to = HashMap::new();
     let mut edge_to_from = HashMap::new();
     for edge in &cg.edges {
@@ -283,21 +1570,26 @@ fn init_graph_inner() -> Result<()> {
             .or_insert_with(HashSet::new);
         entry.insert(edge.from.clone());
     }
-    let state = AppState {
+    Ok(GraphData {
+        source: source.to_owned(),
+        source_hash: file_hash(source)?,
         graph: cg,
         node_id_to_name,
+        node_parent,
+        node_meta,
         edge_from_to,
         edge_to_from,
-        node_id_to_new_name: HashMap::new(),
-        node_set: HashSet::new(),
-    };
-    STATE.lock().unwrap().replace(state);
-    println!("init graph done");
-    Ok(())
+        metrics: OnceLock::new(),
+    })
 }
 
-pub async fn init_graph() -> Json<ResponseStatus> {
-    process_resp(init_graph_inner())
+/// 加载图. 图已初始化时, 不指定 path 则不做任何事; 指定 path 则只替换图数据,
+/// 会话视图和保存的视图保留, 其中已不存在的节点被移除
+pub async fn init_graph(
+    GraphId(graph_id): GraphId,
+    Query(query): Query<InitGraphQuery>,
+) -> Json<ResponseStatus> {
+    process_resp(init_graph_inner(&graph_id, query))
 }
 
 fn gen_node_id_to_name(
@@ -318,6 +1610,45 @@ fn gen_node_id_to_name(
     }
 }
 
+/// 记录每个节点所属的容器节点(文件/impl 等), 文件节点没有父节点
+fn gen_node_parent(
+    nodes: &[HtmlNode],
+    father: Option<&str>,
+    node_parent: &mut HashMap<String, String>,
+) {
+    for node in nodes {
+        if node.id.is_empty() {
+            gen_node_parent(&node.children, father, node_parent);
+            continue;
+        }
+        if let Some(father) = father {
+            if node.id != father {
+                node_parent
+                    .entry(node.id.clone())
+                    .or_insert_with(|| father.to_owned());
+            }
+        }
+        gen_node_parent(&node.children, Some(&node.id), node_parent);
+    }
+}
+
+fn gen_node_meta(nodes: &[HtmlNode], node_meta: &mut HashMap<String, NodeMeta>) {
+    for node in nodes {
+        if !node.id.is_empty() {
+            node_meta
+                .entry(node.id.clone())
+                .or_insert_with(|| NodeMeta {
+                    file: node.file.clone(),
+                    line: node.line,
+                    column: node.column,
+                    end_line: node.end_line,
+                    kind: node.kind,
+                });
+        }
+        gen_node_meta(&node.children, node_meta);
+    }
+}
+
 #[derive(serde::Serialize, Debug)]
 pub struct ResponseStatus {
     pub code: i32,
@@ -343,6 +1674,26 @@ where
     Json(res)
 }
 
+/// 耗时的计算或子进程放到阻塞线程池执行, 不占用 tokio 工作线程
+pub async fn run_blocking<T, F>(f: F) -> anyhow::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+{
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| anyhow::anyhow!("task failed: {}", e))
+        .and_then(|v| v)
+}
+
+pub async fn process_blocking<T, F>(f: F) -> Json<ResponseStatus>
+where
+    T: serde::Serialize + Send + 'static,
+    F: FnOnce() -> anyhow::Result<T> + Send + 'static,
+{
+    process_resp(run_blocking(f).await)
+}
+
 impl ResponseStatus {
     pub fn success() -> Self {
         Self {
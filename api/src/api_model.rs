@@ -1,7 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, OnceLock},
+};
 
-use crate::model::Graph;
+use crate::model::{Graph, NodeKind};
 
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct Node {
@@ -9,18 +12,662 @@ pub struct Node {
     pub name: Option<String>,
 }
 
+/// 带位置信息的节点, 用于搜索结果
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct NodeInfo {
+    pub id: String,
+    pub name: Option<String>,
+    #[serde(flatten)]
+    pub meta: NodeMeta,
+    /// 在编辑器中打开的链接
+    pub open_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct NodeDetail {
+    pub id: String,
+    pub name: String,
+    /// 当前视图中的重命名
+    pub new_name: Option<String>,
+    pub annotation: Option<String>,
+    pub in_view: bool,
+    pub parent: Option<String>,
+    #[serde(flatten)]
+    pub meta: NodeMeta,
+    pub callers: Vec<String>,
+    pub callees: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct SourceQuery {
+    /// 函数前后额外返回的行数, 默认 DEFAULT_CONTEXT_LINES
+    pub context: Option<usize>,
+}
+
+pub const DEFAULT_CONTEXT_LINES: usize = 3;
+pub const MAX_CONTEXT_LINES: usize = 200;
+/// 没有结束行(文件最后一个符号)时最多返回的行数
+pub const MAX_SOURCE_LINES: usize = 500;
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct NodeSource {
+    pub id: String,
+    pub file: String,
+    /// 返回内容的首行行号, 从 1 开始
+    pub start_line: usize,
+    pub end_line: usize,
+    /// 函数本身的行范围
+    pub line: usize,
+    pub node_end_line: usize,
+    pub source: String,
+}
+
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
 pub struct SearchNodeQuery {
     pub related_node_id: Option<String>,
     pub hint_node_id: Option<String>,
+    /// 按指标降序排列结果
+    pub order_by: Option<MetricKind>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    #[default]
+    FanIn,
+    FanOut,
+    /// 传递可达的被调用函数数
+    Reach,
+    /// 传递可达的调用者数
+    ReachedBy,
+    Betweenness,
+    Pagerank,
 }
 
 #[derive(Debug, Deserialize, Serialize, Default, Clone)]
-pub struct AppState {
+pub struct MetricsQuery {
+    pub sort_by: Option<MetricKind>,
+    /// 升序排列, 默认降序
+    pub asc: Option<bool>,
+    pub limit: Option<usize>,
+}
+
+pub const DEFAULT_METRICS_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+pub struct NodeMetrics {
+    pub id: String,
+    pub name: Option<String>,
+    pub fan_in: usize,
+    pub fan_out: usize,
+    pub reach: usize,
+    pub reached_by: usize,
+    pub betweenness: f64,
+    pub pagerank: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct GraphQuery {
+    /// 高亮强连通分量内的边(递归调用), 聚合时高亮分组之间的环
+    pub highlight_cycles: Option<bool>,
+    /// 将函数聚合到所属的文件/容器/目录, 边上标注调用次数
+    pub aggregate: Option<AggregateLevel>,
+    /// 按文件/impl 嵌套输出 subgraph
+    pub group: Option<bool>,
+    pub direction: Option<FlowDirection>,
+    /// 输出格式, 默认 mermaid
+    pub format: Option<ExportFormat>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    #[default]
+    Mermaid,
+    Dot,
+    Plantuml,
+    D2,
+    Graphml,
+    Gexf,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ElementsQuery {
+    pub format: Option<ElementsFormat>,
+    pub highlight_cycles: Option<bool>,
+    pub aggregate: Option<AggregateLevel>,
+    /// 输出分组节点, 并通过 parentId/parent 建立层级
+    pub group: Option<bool>,
+    /// 由服务端计算节点坐标, 默认开启
+    pub layout: Option<bool>,
+    pub direction: Option<FlowDirection>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ElementsFormat {
+    #[default]
+    Reactflow,
+    Cytoscape,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq)]
+pub struct Position {
+    pub x: f64,
+    pub y: f64,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq)]
+pub struct Size {
+    pub width: f64,
+    pub height: f64,
+}
+
+/// React Flow 和 Cytoscape.js 节点中的 data
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ElementNodeData {
+    pub id: String,
+    pub label: String,
+    /// 所属分组(文件/impl)的 id
+    pub group: Option<String>,
+    /// Cytoscape.js 复合节点的父节点, 只在输出分组节点时设置
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent: Option<String>,
+    pub is_group: bool,
+    #[serde(flatten)]
+    pub meta: Option<NodeMeta>,
+    pub annotation: Option<String>,
+    pub open_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ElementEdgeData {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    /// 调用次数大于 1 时为次数, 否则为空
+    pub label: String,
+    pub count: usize,
+    pub in_cycle: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ReactFlowNode {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub node_type: String,
+    pub position: Position,
+    pub data: ElementNodeData,
+    #[serde(rename = "parentId", skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    /// 分组节点的宽高
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub style: Option<Size>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ReactFlowEdge {
+    pub id: String,
+    pub source: String,
+    pub target: String,
+    #[serde(rename = "type")]
+    pub edge_type: String,
+    pub label: String,
+    pub animated: bool,
+    pub data: ElementEdgeData,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ReactFlowGraph {
+    pub nodes: Vec<ReactFlowNode>,
+    pub edges: Vec<ReactFlowEdge>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct CytoscapeNode {
+    pub data: ElementNodeData,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub position: Option<Position>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct CytoscapeEdge {
+    pub data: ElementEdgeData,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct CytoscapeElements {
+    pub nodes: Vec<CytoscapeNode>,
+    pub edges: Vec<CytoscapeEdge>,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(untagged)]
+pub enum GraphElements {
+    Reactflow(ReactFlowGraph),
+    Cytoscape(CytoscapeElements),
+}
+
+/// 视图中渲染的节点, name 已应用重命名
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ViewNode {
+    pub id: String,
+    pub name: String,
+    /// 所属分组(文件/impl)的 id
+    pub group: Option<String>,
+    /// 在编辑器中打开的链接
+    pub open_url: Option<String>,
+    pub meta: Option<NodeMeta>,
+    pub annotation: Option<String>,
+    /// 服务端布局得到的左上角坐标
+    pub position: Option<Position>,
+}
+
+/// 节点所在的容器, name 已应用重命名
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ViewGroup {
+    pub id: String,
+    pub name: String,
+    pub parent: Option<String>,
+    /// 服务端布局得到的外框
+    pub position: Option<Position>,
+    pub size: Option<Size>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ViewEdge {
+    pub from: String,
+    pub to: String,
+    /// 折叠后合并的调用次数
+    pub count: usize,
+    /// 在强连通分量内(递归调用)
+    pub in_cycle: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ViewGraph {
+    pub name: String,
+    pub nodes: Vec<ViewNode>,
+    pub edges: Vec<ViewEdge>,
+    pub groups: Vec<ViewGroup>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct RenderQuery {
+    pub highlight_cycles: Option<bool>,
+    pub direction: Option<FlowDirection>,
+    /// graphviz 布局引擎, 默认 dot
+    pub engine: Option<LayoutEngine>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LayoutEngine {
+    #[default]
+    Dot,
+    Neato,
+    Fdp,
+}
+
+/// mermaid flowchart 的方向
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum FlowDirection {
+    #[default]
+    #[serde(alias = "td")]
+    TD,
+    #[serde(alias = "lr")]
+    LR,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct CollapseQuery {
+    /// 容器节点 id
+    pub id: String,
+    /// 为空时切换当前状态
+    pub collapsed: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AggregateLevel {
+    /// 顶层文件
+    File,
+    /// 直接所属的容器, 即 impl 块或文件
+    Container,
+    /// 文件所在目录
+    Module,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct Cycle {
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    #[default]
+    Json,
+    Markdown,
+    Csv,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct DeadCodeQuery {
+    /// 逗号分隔的入口节点 id
+    pub entry: Option<String>,
+    /// 逗号分隔的入口函数名, 支持 `*` 通配, 如 `main,test_*`
+    pub pattern: Option<String>,
+    pub format: Option<ReportFormat>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct DeadCodeFile {
+    pub file: String,
+    pub nodes: Vec<Node>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct DeadCodeReport {
+    pub entry_points: Vec<Node>,
+    pub total_functions: usize,
+    pub unreachable_count: usize,
+    pub files: Vec<DeadCodeFile>,
+}
+
+/// diff 的来源, 三选一: diff 文本, data_dir 下的 diff 文件, 或对本地仓库执行 `git diff`
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct DiffSource {
+    pub diff: Option<String>,
+    pub diff_path: Option<String>,
+    /// 相对于 source_root 的 git 仓库目录
+    pub repo: Option<String>,
+    /// `git diff` 比较的版本, 默认 HEAD
+    pub base: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct DiffNodesResult {
+    pub changed: Vec<Node>,
+    /// diff 中在图里找不到的文件
+    pub unmatched_files: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ImpactQuery {
+    /// 修改过的函数, 可以是 node id 或函数名
+    #[serde(default)]
+    pub changed: Vec<String>,
+    /// 从 diff 中推导修改过的函数, 与 changed 合并
+    pub diff: Option<DiffSource>,
+    /// 最多向上追溯的调用层数, 为空时不限制
+    pub max_depth: Option<usize>,
+    /// 用受影响的函数替换当前视图
+    pub apply: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ImpactNode {
+    pub id: String,
+    pub name: Option<String>,
+    /// 到最近的修改函数的调用距离, 修改函数本身为 0
+    pub distance: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ImpactResult {
+    pub seeds: Vec<Node>,
+    /// 没有匹配到任何节点的输入
+    pub unresolved: Vec<String>,
+    pub impacted: Vec<ImpactNode>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct TreeQuery {
+    pub id: String,
+    /// 最多展开的层数, 为空时不限制
+    pub depth: Option<usize>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    Callers,
+    #[default]
+    Callees,
+    Both,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ExpandQuery {
+    pub id: String,
+    /// 展开的层数, 默认 1
+    pub depth: Option<usize>,
+    pub direction: Option<Direction>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct PathQuery {
+    pub from: String,
+    pub to: String,
+    /// 只经过当前视图中的节点
+    pub within_view: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct AllPathsQuery {
+    pub from: String,
+    pub to: String,
+    /// 路径最多经过的边数, 默认 `DEFAULT_MAX_DEPTH`, 不超过 `MAX_DEPTH_LIMIT`
+    pub max_depth: Option<usize>,
+    /// 最多返回的路径数, 默认 `DEFAULT_MAX_PATHS`, 不超过 `MAX_PATHS_LIMIT`
+    pub max_paths: Option<usize>,
+    pub within_view: Option<bool>,
+}
+
+pub const DEFAULT_MAX_DEPTH: usize = 10;
+pub const DEFAULT_MAX_PATHS: usize = 100;
+pub const MAX_DEPTH_LIMIT: usize = 50;
+pub const MAX_PATHS_LIMIT: usize = 10000;
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct AllPathsResult {
+    pub paths: Vec<Vec<Node>>,
+    /// 达到 max_paths 后停止搜索, 可能还有更多路径
+    pub truncated: bool,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct InitGraphQuery {
+    /// 相对于 data_dir 的 dot 文件路径
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct NodeAnnotation {
+    pub id: String,
+    /// 为空时删除注释
+    pub annotation: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct WorkspaceQuery {
+    pub name: String,
+    /// 源文件已变化时仍然加载
+    pub force: Option<bool>,
+}
+
+/// 保存到磁盘的工作区
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct Workspace {
+    pub source: String,
+    pub source_hash: String,
+    pub saved_at: u64,
+    pub view: ViewState,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct WorkspaceLoadResult {
+    pub source: String,
+    pub hash_matched: bool,
+    /// 图中已不存在而被丢弃的节点数
+    pub dropped_nodes: usize,
+    pub warning: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct GraphInfo {
+    pub id: String,
+    pub source: String,
+    pub node_count: usize,
+    pub edge_count: usize,
+    pub view_count: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct SessionInfo {
+    pub session_id: String,
+    pub created_at: u64,
+    /// `editors` 中配置的编辑器名, 不接受任意 URI 模板, 为空时使用配置中的 default_editor
+    #[serde(default)]
+    pub editor: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct EditorQuery {
+    /// 为空时清除会话的选择
+    pub editor: Option<String>,
+}
+
+/// 节点在源码中的位置和类别, 来自 `HtmlNode`
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq, Eq)]
+pub struct NodeMeta {
+    pub file: String,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub end_line: Option<usize>,
+    pub kind: NodeKind,
+}
+
+/// 一个图的解析结果, 加载后只读, 在所有会话间共享
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct GraphData {
+    pub source: String,
+    /// 加载时源文件内容的 sha256
+    pub source_hash: String,
     pub graph: Graph,
     pub node_id_to_name: HashMap<String, String>,
+    /// node id -> 所属容器(文件/impl 等)的 node id
+    pub node_parent: HashMap<String, String>,
+    pub node_meta: HashMap<String, NodeMeta>,
     pub edge_from_to: HashMap<String, HashSet<String>>,
     pub edge_to_from: HashMap<String, HashSet<String>>,
+    /// 按需计算的节点指标, 见 `analysis::metrics`
+    #[serde(skip)]
+    pub metrics: OnceLock<HashMap<String, NodeMetrics>>,
+}
+
+/// 某个会话在图上的工作视图
+#[derive(Debug, Deserialize, Serialize, Default, Clone, PartialEq)]
+#[serde(default)]
+pub struct ViewState {
     pub node_id_to_new_name: HashMap<String, String>,
     pub node_set: HashSet<String>,
+    pub node_annotations: HashMap<String, String>,
+    /// 折叠显示为单个节点的容器(文件/impl 等)
+    pub collapsed: HashSet<String>,
+}
+
+impl ViewState {
+    /// 去掉图中已不存在的节点
+    pub fn prune(&mut self, data: &GraphData) {
+        let exists = |id: &String| data.node_id_to_name.contains_key(id);
+        self.node_set.retain(exists);
+        self.node_id_to_new_name.retain(|id, _| exists(id));
+        self.node_annotations.retain(|id, _| exists(id));
+        self.collapsed.retain(exists);
+    }
+}
+
+/// 一次修改视图的操作, `snapshot` 保存操作另一侧的视图, 撤销/重做时与当前视图交换
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct Operation {
+    pub op: String,
+    pub args: serde_json::Value,
+    pub timestamp: u64,
+    #[serde(skip)]
+    pub snapshot: ViewState,
+}
+
+pub const HISTORY_LIMIT: usize = 100;
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ViewHistory {
+    pub applied: Vec<Operation>,
+    pub undone: Vec<Operation>,
+}
+
+impl ViewHistory {
+    pub fn record(&mut self, op: Operation) {
+        self.applied.push(op);
+        if self.applied.len() > HISTORY_LIMIT {
+            self.applied.remove(0);
+        }
+        self.undone.clear();
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct SessionView {
+    pub view: ViewState,
+    pub history: ViewHistory,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct SavedView {
+    pub name: String,
+    pub created_at: u64,
+    pub view: ViewState,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct SavedViewInfo {
+    pub name: String,
+    pub created_at: u64,
+    pub node_count: usize,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct SavedViewQuery {
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ViewDiffQuery {
+    pub from: String,
+    /// 为空时与当前视图比较
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct RenameDiff {
+    pub id: String,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct ViewDiff {
+    pub added_nodes: Vec<Node>,
+    pub removed_nodes: Vec<Node>,
+    pub renamed: Vec<RenameDiff>,
+}
+
+#[derive(Debug, Deserialize, Serialize, Default, Clone)]
+pub struct AppState {
+    pub data: Arc<GraphData>,
+    /// session id -> 视图
+    pub views: HashMap<String, SessionView>,
+    /// 命名保存的视图, 同一个图的所有会话共享
+    pub saved_views: HashMap<String, SavedView>,
 }
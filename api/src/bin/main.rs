@@ -1,10 +1,53 @@
 use anyhow::{Context, Result};
-use api::dot_parse::write_to_file;
+use api::{
+    analysis,
+    api_model::{ExportFormat, FlowDirection, ViewState},
+    dot_parse::write_to_file,
+    export::{self, ExportOptions},
+};
+use clap::Parser;
+
+#[derive(Debug, Parser)]
+struct Args {
+    #[arg(long, default_value = "data/master.dot")]
+    dot_path: String,
+    /// 输出文件, 不指定 format 时默认 data/master.json, 指定 format 时默认输出到标准输出
+    #[arg(long)]
+    out: Option<String>,
+    /// 将所有函数导出为指定格式, 不指定时输出解析结果的 json
+    #[arg(long, value_enum)]
+    format: Option<ExportFormat>,
+    #[arg(long, value_enum, default_value = "td")]
+    direction: FlowDirection,
+    /// 按文件/impl 分组
+    #[arg(long)]
+    group: bool,
+}
 
 #[auto_context::auto_context]
 fn main() -> Result<()> {
-    let cg = api::dot_parse::parse_from_dot("data/master.dot")?;
-    println!("{:?}", cg);
-    write_to_file(&serde_json::to_string(&cg)?, "data/master.json")?;
+    let args = Args::parse();
+    let Some(format) = args.format else {
+        let cg = api::dot_parse::parse_from_dot(&args.dot_path)?;
+        println!("{:?}", cg);
+        let out = args.out.as_deref().unwrap_or("data/master.json");
+        write_to_file(&serde_json::to_string(&cg)?, out)?;
+        return Ok(());
+    };
+    let data = api::api::load_graph(&args.dot_path)?;
+    let view = ViewState {
+        node_set: analysis::function_ids(&data).into_iter().cloned().collect(),
+        ..Default::default()
+    };
+    let graph = export::view_graph(&data, &view, false);
+    let options = ExportOptions {
+        direction: args.direction,
+        group: args.group,
+    };
+    let content = export::exporter(format, options).export(&graph);
+    match &args.out {
+        Some(out) => write_to_file(&content, out)?,
+        None => print!("{}", content),
+    }
     Ok(())
 }
@@ -1,10 +1,15 @@
 use anyhow::{Context, Result};
+use api::config::{Config, ServerArgs, CONFIG};
+use clap::Parser;
 
 #[auto_context::auto_context]
 #[tokio::main]
 async fn main() -> Result<()> {
+    let config = Config::from_args(ServerArgs::parse())?;
+    let bind_addr = config.bind_addr.clone();
+    *CONFIG.lock().unwrap() = config;
     let app = api::api::routes();
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:4096").await?;
+    let listener = tokio::net::TcpListener::bind(&bind_addr).await?;
     axum::serve(listener, app.into_make_service()).await?;
     Ok(())
 }
@@ -0,0 +1,172 @@
+use anyhow::{Context, Result};
+use clap::Parser;
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+/// 链接中的路径保留 `/` 和 `:`, 其余保留字符和空格编码
+const PATH_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'/')
+    .remove(b':')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'.')
+    .remove(b'~');
+
+lazy_static::lazy_static! {
+    pub static ref CONFIG: Mutex<Config> = Mutex::new(Config::default());
+}
+
+#[derive(Debug, Deserialize, Serialize, Clone)]
+#[serde(default)]
+pub struct Config {
+    /// `/init_graph` 未指定 path 时加载的 dot 文件
+    pub dot_path: String,
+    pub bind_addr: String,
+    /// 运行时 `/init_graph?path=...` 只允许加载该目录下的文件
+    pub data_dir: String,
+    pub workspace_dir: String,
+    /// 被分析项目的源码根目录, `/node/:id/source` 只读取该目录下的文件
+    pub source_root: Option<String>,
+    /// 编辑器名 -> 打开文件的 URI 模板, 支持 `{path}` `{line}` `{column}` 占位符, 会话只能选择其中的编辑器
+    pub editors: HashMap<String, String>,
+    /// 会话未选择编辑器时使用, 为空则不生成链接
+    pub default_editor: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            dot_path: "data/master.dot".to_owned(),
+            bind_addr: "0.0.0.0:4096".to_owned(),
+            data_dir: "data".to_owned(),
+            workspace_dir: "data/workspaces".to_owned(),
+            source_root: None,
+            editors: HashMap::from([
+                (
+                    "vscode".to_owned(),
+                    "vscode://file/{path}:{line}:{column}".to_owned(),
+                ),
+                (
+                    "idea".to_owned(),
+                    "idea://open?file={path}&line={line}".to_owned(),
+                ),
+                (
+                    "sublime".to_owned(),
+                    "subl://open?url=file://{path}&line={line}".to_owned(),
+                ),
+            ]),
+            default_editor: None,
+        }
+    }
+}
+
+#[derive(Debug, Parser)]
+pub struct ServerArgs {
+    /// json 配置文件, 命令行参数优先
+    #[arg(long)]
+    pub config: Option<String>,
+    #[arg(long)]
+    pub dot_path: Option<String>,
+    #[arg(long)]
+    pub bind_addr: Option<String>,
+    #[arg(long)]
+    pub data_dir: Option<String>,
+    #[arg(long)]
+    pub workspace_dir: Option<String>,
+    #[arg(long)]
+    pub source_root: Option<String>,
+    #[arg(long)]
+    pub default_editor: Option<String>,
+}
+
+impl Config {
+    #[auto_context::auto_context]
+    pub fn from_file(path: &str) -> Result<Self> {
+        let raw = std::fs::read_to_string(path)?;
+        let config = serde_json::from_str(&raw)?;
+        Ok(config)
+    }
+
+    #[auto_context::auto_context]
+    pub fn from_args(args: ServerArgs) -> Result<Self> {
+        let mut config = match &args.config {
+            Some(path) => Self::from_file(path)?,
+            None => Self::default(),
+        };
+        if let Some(dot_path) = args.dot_path {
+            config.dot_path = dot_path;
+        }
+        if let Some(bind_addr) = args.bind_addr {
+            config.bind_addr = bind_addr;
+        }
+        if let Some(data_dir) = args.data_dir {
+            config.data_dir = data_dir;
+        }
+        if let Some(workspace_dir) = args.workspace_dir {
+            config.workspace_dir = workspace_dir;
+        }
+        if args.source_root.is_some() {
+            config.source_root = args.source_root;
+        }
+        if args.default_editor.is_some() {
+            config.default_editor = args.default_editor;
+        }
+        Ok(config)
+    }
+
+    /// 编辑器名解析为配置中的模板, 只允许 `editors` 中配置的编辑器
+    #[auto_context::auto_context]
+    pub fn editor_template(&self, editor: &str) -> Result<String> {
+        self.editors
+            .get(editor)
+            .cloned()
+            .ok_or(anyhow::anyhow!("unknown editor {}", editor))
+    }
+
+    /// 用模板生成打开文件的链接, 配置了 source_root 时使用绝对路径, 路径经过百分号编码
+    pub fn open_url(&self, template: &str, file: &str, line: usize, column: usize) -> String {
+        let path = match &self.source_root {
+            Some(root) => Path::new(root)
+                .canonicalize()
+                .unwrap_or_else(|_| PathBuf::from(root))
+                .join(file),
+            None => PathBuf::from(file),
+        };
+        template
+            .replace(
+                "{path}",
+                &utf8_percent_encode(&path.to_string_lossy(), PATH_ENCODE_SET).to_string(),
+            )
+            .replace("{line}", &line.to_string())
+            .replace("{column}", &column.to_string())
+    }
+}
+
+pub fn get_config() -> Config {
+    CONFIG.lock().unwrap().clone()
+}
+
+/// 将请求中的路径解析到 `root` 之下, 拒绝越界访问
+#[auto_context::auto_context]
+pub fn resolve_within(root: &str, path: &str) -> Result<PathBuf> {
+    let root = Path::new(root)
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("root dir {} not accessible: {}", root, e))?;
+    let joined = root.join(path);
+    let resolved = joined
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("path {} not accessible: {}", path, e))?;
+    if !resolved.starts_with(&root) {
+        return Err(anyhow::anyhow!(
+            "path {} is outside of {}",
+            path,
+            root.display()
+        ));
+    }
+    Ok(resolved)
+}
@@ -0,0 +1,252 @@
+use anyhow::{Context, Result};
+use std::process::Command;
+
+/// 一个文件在 diff 中被修改的行, 行号为新文件中的行号
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileChange {
+    pub path: String,
+    pub lines: Vec<usize>,
+    /// 文件被删除, 其中所有函数都视为修改
+    pub whole_file: bool,
+}
+
+fn strip_diff_path(path: &str) -> Option<String> {
+    let path = path.split('\t').next().unwrap_or_default().trim();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_owned())
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    let mut parts = range.strip_prefix(['-', '+'])?.split(',');
+    let start = parts.next()?.parse().ok()?;
+    let count = match parts.next() {
+        Some(count) => count.parse().ok()?,
+        None => 1,
+    };
+    Some((start, count))
+}
+
+/// 解析 `@@ -a,b +c,d @@`, 返回 (旧文件行数, 新文件起始行, 新文件行数)
+fn parse_hunk_header(header: &str) -> Option<(usize, usize, usize)> {
+    let mut parts = header.split_whitespace().skip(1);
+    let (_, old_count) = parse_range(parts.next()?)?;
+    let (new_start, new_count) = parse_range(parts.next()?)?;
+    Some((old_count, new_start, new_count))
+}
+
+/// 解析 unified diff, 记录新增行以及删除发生的位置
+pub fn parse_unified_diff(diff: &str) -> Vec<FileChange> {
+    let mut changes: Vec<FileChange> = Vec::new();
+    let mut old_path = None;
+    let mut new_line = 0;
+    // 当前 hunk 中剩余的旧/新文件行数
+    let (mut old_left, mut new_left) = (0, 0);
+    for line in diff.lines() {
+        let in_hunk = old_left > 0 || new_left > 0;
+        if let Some(path) = line.strip_prefix("--- ").filter(|_| !in_hunk) {
+            old_path = strip_diff_path(path);
+            continue;
+        }
+        if let Some(path) = line.strip_prefix("+++ ").filter(|_| !in_hunk) {
+            let change = match strip_diff_path(path) {
+                Some(path) => FileChange {
+                    path,
+                    ..Default::default()
+                },
+                None => FileChange {
+                    path: old_path.clone().unwrap_or_default(),
+                    whole_file: true,
+                    ..Default::default()
+                },
+            };
+            changes.push(change);
+            continue;
+        }
+        if line.starts_with("@@") && !in_hunk {
+            if let Some((old_count, new_start, new_count)) = parse_hunk_header(line) {
+                (old_left, new_line, new_left) = (old_count, new_start, new_count);
+                // 纯删除的 hunk 中新文件起始行指向删除位置之前的一行
+                if new_count == 0 {
+                    new_line += 1;
+                }
+            }
+            continue;
+        }
+        let Some(change) = changes.last_mut().filter(|_| in_hunk) else {
+            continue;
+        };
+        if line.starts_with('+') {
+            change.lines.push(new_line);
+            new_line += 1;
+            new_left = new_left.saturating_sub(1);
+        } else if line.starts_with('-') {
+            // 删除的行落在新文件当前位置
+            change.lines.push(new_line.max(1));
+            old_left = old_left.saturating_sub(1);
+        } else if !line.starts_with('\\') {
+            new_line += 1;
+            old_left = old_left.saturating_sub(1);
+            new_left = new_left.saturating_sub(1);
+        }
+    }
+    for change in changes.iter_mut() {
+        change.lines.sort();
+        change.lines.dedup();
+    }
+    changes.retain(|change| change.whole_file || !change.lines.is_empty());
+    changes
+}
+
+/// 在本地仓库中执行 `git diff <base>`, 禁用仓库配置中的外部 diff 和 textconv 命令
+#[auto_context::auto_context]
+pub fn git_diff(repo: &str, base: &str) -> Result<String> {
+    if base.starts_with('-') {
+        return Err(anyhow::anyhow!("invalid git revision {}", base));
+    }
+    if !std::path::Path::new(repo).is_dir() {
+        return Err(anyhow::anyhow!("repository {} not found", repo));
+    }
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(["-c", "diff.external=", "-c", "core.fsmonitor="])
+        .args([
+            "diff",
+            "--no-ext-diff",
+            "--no-textconv",
+            "--no-color",
+            "--unified=0",
+            base,
+            "--",
+        ])
+        .output()
+        .map_err(|e| anyhow::anyhow!("failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow::anyhow!(
+            "git diff failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// 路径相同, 或其中一个是另一个以目录为边界的后缀
+pub fn path_matches(a: &str, b: &str) -> bool {
+    a == b || a.ends_with(&format!("/{}", b)) || b.ends_with(&format!("/{}", a))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn change(path: &str, lines: &[usize], whole_file: bool) -> FileChange {
+        FileChange {
+            path: path.to_owned(),
+            lines: lines.to_vec(),
+            whole_file,
+        }
+    }
+
+    #[test]
+    fn rename_uses_new_path() {
+        let diff = "\
+diff --git a/src/old.rs b/src/new.rs
+similarity index 90%
+rename from src/old.rs
+rename to src/new.rs
+--- a/src/old.rs
++++ b/src/new.rs
+@@ -3 +3 @@ fn main() {
+-    old();
++    new();
+";
+        assert_eq!(
+            parse_unified_diff(diff),
+            vec![change("src/new.rs", &[3], false)]
+        );
+    }
+
+    #[test]
+    fn added_and_deleted_files() {
+        let diff = "\
+--- /dev/null
++++ b/src/added.rs
+@@ -0,0 +1,2 @@
++fn a() {}
++fn b() {}
+--- a/src/deleted.rs
++++ /dev/null
+@@ -1,2 +0,0 @@
+-fn c() {}
+-fn d() {}
+";
+        assert_eq!(
+            parse_unified_diff(diff),
+            vec![
+                change("src/added.rs", &[1, 2], false),
+                change("src/deleted.rs", &[1], true),
+            ]
+        );
+    }
+
+    #[test]
+    fn pure_deletion_hunk() {
+        let diff = "\
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -10,2 +9,0 @@ impl Foo {
+-    let a = 1;
+-    let b = 2;
+";
+        assert_eq!(
+            parse_unified_diff(diff),
+            vec![change("src/lib.rs", &[10], false)]
+        );
+    }
+
+    #[test]
+    fn multiple_files_and_hunks() {
+        let diff = "\
+--- a/src/a.rs
++++ b/src/a.rs
+@@ -1,3 +1,3 @@
+ fn a() {
+-    1
++    2
+ }
+@@ -20 +20,2 @@
+-x
++y
++z
+--- a/src/b.rs
++++ b/src/b.rs
+@@ -5,0 +6 @@
++added
+";
+        assert_eq!(
+            parse_unified_diff(diff),
+            vec![
+                change("src/a.rs", &[2, 20, 21], false),
+                change("src/b.rs", &[6], false),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_hunk_header_is_ignored() {
+        let diff = "--- a/x\n+++ b/x\n@@ é1 +1 @@\n+a\n";
+        assert!(parse_unified_diff(diff).is_empty());
+    }
+
+    #[test]
+    fn path_suffix_matches_on_directory_boundary() {
+        assert!(path_matches("/repo/src/main.rs", "src/main.rs"));
+        assert!(!path_matches("src/xmain.rs", "main.rs"));
+    }
+}
@@ -1,3 +1,4 @@
+use crate::model::NodeKind;
 use anyhow::{Context, Result};
 use graphviz_rust::dot_structures::*;
 use graphviz_rust::parse;
@@ -32,12 +33,8 @@ fn check_node(node: Node) -> Result<crate::model::HtmlNode> {
         }
     }
     match id2 {
-        Id::Html(html) => {
-            return check_html(html);
-        }
-        _ => {
-            return Err(anyhow::anyhow!("check internal attribute id"));
-        }
+        Id::Html(html) => check_html(html),
+        _ => Err(anyhow::anyhow!("check internal attribute id")),
     }
 }
 
@@ -190,10 +187,10 @@ fn check_html_node<'a>(
     if html_node.id.is_empty() {
         let idd = html_node
             .children
-            .get(0)
+            .first()
             .map(|v| v.id.clone())
             .map(|v| v.split(":").map(|v| v.to_string()).collect::<Vec<String>>())
-            .and_then(|v| v.get(0).cloned())
+            .and_then(|v| v.first().cloned())
             .unwrap_or_default();
         html_node.id = idd;
     }
@@ -203,15 +200,16 @@ fn check_html_node<'a>(
 
 #[auto_context::auto_context]
 fn raw_html_node_to_html_node(raw: crate::model::HtmlNodeRaw) -> crate::model::HtmlNode {
-    let mut ans = crate::model::HtmlNode::default();
-    ans.id = raw.id;
-    ans.text = raw.text;
-    ans.children = raw
-        .children
-        .into_iter()
-        .map(|v| raw_html_node_to_html_node(v))
-        .collect();
-    ans
+    crate::model::HtmlNode {
+        id: raw.id,
+        text: raw.text,
+        children: raw
+            .children
+            .into_iter()
+            .map(raw_html_node_to_html_node)
+            .collect(),
+        ..Default::default()
+    }
 }
 
 #[auto_context::auto_context]
@@ -226,6 +224,84 @@ fn check_html(raw_html: String) -> Result<crate::model::HtmlNode> {
     Ok(raw_html_node_to_html_node(html_node))
 }
 
+/// crabviz 的节点 id 为 `文件id:行_列`, 行列从 0 开始, 这里转换为从 1 开始
+fn parse_position(id: &str) -> Option<(usize, usize)> {
+    let (_, port) = id.split_once(':')?;
+    let (line, col) = port.split_once('_')?;
+    let number = |v: &str| {
+        v.chars()
+            .take_while(|c| c.is_ascii_digit())
+            .collect::<String>()
+            .parse::<usize>()
+            .ok()
+    };
+    Some((number(line)? + 1, number(col)? + 1))
+}
+
+/// 带子节点的是 impl 或结构体, 其中的叶子节点是方法, 否则是函数
+fn node_kind(node: &crate::model::HtmlNode, parent: NodeKind) -> NodeKind {
+    if !node.children.is_empty() {
+        if node.text.trim_start().starts_with("impl") {
+            NodeKind::Impl
+        } else {
+            NodeKind::Struct
+        }
+    } else if matches!(parent, NodeKind::Impl | NodeKind::Struct) {
+        NodeKind::Method
+    } else {
+        NodeKind::Function
+    }
+}
+
+fn fill_node_location(node: &mut crate::model::HtmlNode, file: &str, starts: &mut Vec<usize>) {
+    node.file = file.to_owned();
+    if let Some((line, column)) = parse_position(&node.id) {
+        node.line = Some(line);
+        node.column = Some(column);
+        starts.push(line);
+    }
+    let kind = node.kind;
+    for child in node.children.iter_mut() {
+        child.kind = node_kind(child, kind);
+        fill_node_location(child, file, starts);
+    }
+}
+
+/// 函数的结束行取同文件中下一个符号起始行的前一行, 容器的结束行取最后一个子节点的结束行
+fn fill_node_end_line(node: &mut crate::model::HtmlNode, starts: &[usize]) {
+    for child in node.children.iter_mut() {
+        fill_node_end_line(child, starts);
+    }
+    if node.children.is_empty() {
+        node.end_line = node.line.and_then(|line| {
+            starts
+                .iter()
+                .find(|start| **start > line)
+                .map(|start| start - 1)
+        });
+    } else if node.line.is_some() {
+        node.end_line = node.children.iter().filter_map(|v| v.end_line).max();
+        if node
+            .children
+            .iter()
+            .any(|v| v.line.is_some() && v.end_line.is_none())
+        {
+            node.end_line = None;
+        }
+    }
+}
+
+/// 顶层节点是文件, 其文本为文件路径, 为文件内的所有节点填充位置信息
+fn fill_location(file_node: &mut crate::model::HtmlNode) {
+    let file = file_node.text.trim_end_matches(':').to_owned();
+    let mut starts = Vec::new();
+    file_node.kind = NodeKind::File;
+    fill_node_location(file_node, &file, &mut starts);
+    starts.sort();
+    starts.dedup();
+    fill_node_end_line(file_node, &starts);
+}
+
 fn check_graph_node(node: &crate::model::HtmlNode, node_set: &mut HashSet<String>) -> Result<()> {
     node_set.insert(node.id.clone());
     for child in node.children.iter() {
@@ -262,11 +338,15 @@ pub fn parse_from_dot(dot_path: &str) -> Result<crate::model::Graph> {
         parse(&raw_dot).map_err(|e| anyhow::anyhow!("Failed to parse dot file: {}", e))?;
     let mut cg = crate::model::Graph::default();
     if let Graph::DiGraph { id, stmts, .. } = g {
-        cg.id = format!("{}", id).replace("\"", "");
+        cg.id = match id {
+            Id::Html(id) | Id::Escaped(id) | Id::Plain(id) | Id::Anonymous(id) => id,
+        }
+        .replace("\"", "");
         for stmt in stmts {
             match stmt {
                 Stmt::Node(node) => {
-                    let node_html = check_node(node)?;
+                    let mut node_html = check_node(node)?;
+                    fill_location(&mut node_html);
                     cg.nodes.push(node_html)
                 }
                 Stmt::Edge(edge) => {
@@ -0,0 +1,1114 @@
+use crate::{
+    analysis,
+    api_model::{
+        AggregateLevel, CytoscapeEdge, CytoscapeElements, CytoscapeNode, ElementEdgeData,
+        ElementNodeData, ExportFormat, FlowDirection, GraphData, LayoutEngine, Position,
+        ReactFlowEdge, ReactFlowGraph, ReactFlowNode, ViewEdge, ViewGraph, ViewGroup, ViewNode,
+        ViewState,
+    },
+    layout,
+};
+use anyhow::Result;
+use graphviz_rust::{
+    cmd::{CommandArg, Format, Layout},
+    dot_structures::{
+        Attribute, Edge, EdgeTy, Graph, GraphAttributes, Id, Node, NodeId, Stmt, Subgraph, Vertex,
+    },
+    printer::{DotPrinter, PrinterContext},
+};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// 当前视图中实际要渲染的节点和边, 折叠的容器代替其中的节点, 连向内部节点的边改连到容器上
+pub fn view_graph(data: &GraphData, view: &ViewState, highlight_cycles: bool) -> ViewGraph {
+    // node id -> 所在强连通分量的下标
+    let mut node_component = HashMap::new();
+    if highlight_cycles {
+        for (i, component) in analysis::strongly_connected_components(data)
+            .into_iter()
+            .enumerate()
+        {
+            node_component.extend(component.into_iter().map(|id| (id, i)));
+        }
+    }
+    let represent =
+        |id: &'_ str| analysis::collapsed_ancestor(data, &view.collapsed, id).to_owned();
+    let mut members: BTreeMap<String, usize> = BTreeMap::new();
+    for id in &view.node_set {
+        let target = represent(id);
+        let count = members.entry(target.clone()).or_insert(0);
+        if target != *id {
+            *count += 1;
+        }
+    }
+    // 容器 id 中可能含有 `>` 等字符, 折叠后的节点使用 `c{i}` 作为 id
+    let alias: HashMap<String, String> = members
+        .keys()
+        .filter(|id| view.collapsed.contains(*id))
+        .enumerate()
+        .map(|(i, id)| (id.clone(), format!("c{}", i)))
+        .collect();
+    let view_id = |id: String| alias.get(&id).cloned().unwrap_or(id);
+    let mut edges: Vec<ViewEdge> = Vec::new();
+    let mut edge_index = HashMap::new();
+    for (from, tos) in &data.edge_from_to {
+        for to in tos {
+            if !view.node_set.contains(to) || !view.node_set.contains(from) {
+                continue;
+            }
+            let (a, b) = (represent(from), represent(to));
+            if a == b && (a != *from || b != *to) {
+                continue;
+            }
+            let in_cycle = matches!(
+                (node_component.get(from), node_component.get(to)),
+                (Some(x), Some(y)) if x == y
+            );
+            let i = *edge_index.entry((a.clone(), b.clone())).or_insert_with(|| {
+                edges.push(ViewEdge {
+                    from: view_id(a),
+                    to: view_id(b),
+                    ..Default::default()
+                });
+                edges.len() - 1
+            });
+            edges[i].count += 1;
+            edges[i].in_cycle |= in_cycle;
+        }
+    }
+    // 节点和边按 id 排序, 同一视图每次输出相同
+    edges.sort_by(|a, b| (&a.from, &a.to).cmp(&(&b.from, &b.to)));
+    let mut nodes = Vec::new();
+    for (id, count) in members {
+        let collapsed = view.collapsed.contains(&id);
+        let mut name = match view.node_id_to_new_name.get(&id) {
+            Some(new_name) => new_name.clone(),
+            None if collapsed => analysis::display_name(data, &id),
+            None => data.node_id_to_name.get(&id).cloned().unwrap_or(id.clone()),
+        };
+        if collapsed {
+            name = format!("{} ({})", name, count);
+        }
+        nodes.push(ViewNode {
+            group: data.node_parent.get(&id).cloned(),
+            meta: data.node_meta.get(&id).cloned(),
+            annotation: view.node_annotations.get(&id).cloned(),
+            id: view_id(id),
+            name,
+            ..Default::default()
+        });
+    }
+    ViewGraph {
+        name: data.graph.id.clone(),
+        groups: view_groups(data, view, &nodes),
+        nodes,
+        edges,
+    }
+}
+
+/// 节点所在的容器及其所有祖先容器, 按 id 排序
+fn view_groups(data: &GraphData, view: &ViewState, nodes: &[ViewNode]) -> Vec<ViewGroup> {
+    let mut groups = BTreeMap::new();
+    for node in nodes {
+        let mut container = node.group.as_deref();
+        while let Some(id) = container {
+            if groups.contains_key(id) {
+                break;
+            }
+            let name = view
+                .node_id_to_new_name
+                .get(id)
+                .cloned()
+                .unwrap_or_else(|| analysis::display_name(data, id));
+            let parent = data.node_parent.get(id);
+            groups.insert(
+                id.to_owned(),
+                ViewGroup {
+                    id: id.to_owned(),
+                    name,
+                    parent: parent.cloned(),
+                    ..Default::default()
+                },
+            );
+            container = parent.map(|v| v.as_str());
+        }
+    }
+    groups.into_values().collect()
+}
+
+/// 将视图中的节点聚合到文件/容器/目录, 节点 id 使用分组下标, 避免容器 id 中的特殊字符.
+/// 高亮环时标出分组之间构成环的边, 例如互相调用的两个文件
+pub fn aggregated_view_graph(
+    data: &GraphData,
+    view: &ViewState,
+    level: AggregateLevel,
+    highlight_cycles: bool,
+) -> ViewGraph {
+    let (groups, edges) = analysis::aggregate(data, &view.node_set, level);
+    // 分组 -> 所在强连通分量的下标
+    let mut group_component = HashMap::new();
+    if highlight_cycles {
+        let mut group_edges: HashMap<String, HashSet<String>> = HashMap::new();
+        for (from, to) in edges.keys() {
+            group_edges
+                .entry(from.clone())
+                .or_default()
+                .insert(to.clone());
+        }
+        for (i, component) in analysis::cycle_components(&group_edges)
+            .into_iter()
+            .enumerate()
+        {
+            group_component.extend(component.into_iter().map(|id| (id, i)));
+        }
+    }
+    let mut group_index = HashMap::new();
+    let mut graph = ViewGraph {
+        name: data.graph.id.clone(),
+        ..Default::default()
+    };
+    for (i, (group, count)) in groups.iter().enumerate() {
+        let name = match level {
+            AggregateLevel::Module => group.clone(),
+            _ => view
+                .node_id_to_new_name
+                .get(group)
+                .cloned()
+                .unwrap_or_else(|| analysis::display_name(data, group)),
+        };
+        graph.nodes.push(ViewNode {
+            id: format!("g{}", i),
+            name: format!("{} ({})", name, count),
+            ..Default::default()
+        });
+        group_index.insert(group, i);
+    }
+    for ((from, to), count) in &edges {
+        let in_cycle = matches!(
+            (group_component.get(from), group_component.get(to)),
+            (Some(x), Some(y)) if x == y
+        );
+        graph.edges.push(ViewEdge {
+            from: format!("g{}", group_index[from]),
+            to: format!("g{}", group_index[to]),
+            count: *count,
+            in_cycle,
+        });
+    }
+    graph
+}
+
+/// 将视图导出为某种文本格式
+pub trait Exporter {
+    fn export(&self, graph: &ViewGraph) -> String;
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExportOptions {
+    pub direction: FlowDirection,
+    /// 按文件/impl 分组, dot 总是按文件分组
+    pub group: bool,
+}
+
+pub fn exporter(format: ExportFormat, options: ExportOptions) -> Box<dyn Exporter> {
+    match format {
+        ExportFormat::Mermaid => Box::new(MermaidExporter(options)),
+        ExportFormat::Dot => Box::new(DotExporter(options)),
+        ExportFormat::Plantuml => Box::new(PlantUmlExporter(options)),
+        ExportFormat::D2 => Box::new(D2Exporter(options)),
+        ExportFormat::Graphml => Box::new(GraphMlExporter),
+        ExportFormat::Gexf => Box::new(GexfExporter),
+    }
+}
+
+/// 分组 -> 直接子分组, 分组 -> 直接包含的节点, 以及不属于任何分组的节点
+struct GroupTree<'a> {
+    sub_groups: BTreeMap<&'a str, Vec<&'a ViewGroup>>,
+    group_nodes: BTreeMap<&'a str, Vec<&'a ViewNode>>,
+    top_groups: Vec<&'a ViewGroup>,
+    top_nodes: Vec<&'a ViewNode>,
+}
+
+impl<'a> GroupTree<'a> {
+    fn new(graph: &'a ViewGraph) -> Self {
+        let mut tree = GroupTree {
+            sub_groups: BTreeMap::new(),
+            group_nodes: BTreeMap::new(),
+            top_groups: Vec::new(),
+            top_nodes: Vec::new(),
+        };
+        for group in &graph.groups {
+            match &group.parent {
+                Some(parent) => tree.sub_groups.entry(parent).or_default().push(group),
+                None => tree.top_groups.push(group),
+            }
+        }
+        for node in &graph.nodes {
+            match &node.group {
+                Some(group) => tree.group_nodes.entry(group).or_default().push(node),
+                None => tree.top_nodes.push(node),
+            }
+        }
+        tree
+    }
+
+    fn sub_groups(&self, group: &str) -> &[&'a ViewGroup] {
+        self.sub_groups
+            .get(group)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    fn nodes(&self, group: &str) -> &[&'a ViewNode] {
+        self.group_nodes
+            .get(group)
+            .map(|v| v.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// 分组及其所有子分组中的节点
+    fn all_nodes(&self, group: &str) -> Vec<&'a ViewNode> {
+        let mut nodes = self.nodes(group).to_vec();
+        for sub in self.sub_groups(group) {
+            nodes.extend(self.all_nodes(&sub.id));
+        }
+        nodes
+    }
+}
+
+/// mermaid 标签加引号, 名称中的括号等字符不会被当作节点形状
+fn mermaid_label(name: &str) -> String {
+    format!("[\"{}\"]", name.replace('"', "#quot;"))
+}
+
+/// 与 `/graph` 原有输出一致的 mermaid flowchart
+pub struct MermaidExporter(pub ExportOptions);
+
+impl MermaidExporter {
+    /// subgraph id 使用序号以免与节点 id 冲突
+    fn push_subgraph(
+        tree: &GroupTree,
+        group: &ViewGroup,
+        depth: usize,
+        next_id: &mut usize,
+        out: &mut String,
+    ) {
+        let indent = "    ".repeat(depth);
+        out.push_str(&format!(
+            "{}subgraph sg{} {}\n",
+            indent,
+            next_id,
+            mermaid_label(&group.name)
+        ));
+        *next_id += 1;
+        for node in tree.nodes(&group.id) {
+            out.push_str(&format!(
+                "{}    {}{}\n",
+                indent,
+                node.id,
+                mermaid_label(&node.name)
+            ));
+        }
+        for sub in tree.sub_groups(&group.id) {
+            Self::push_subgraph(tree, sub, depth + 1, next_id, out);
+        }
+        out.push_str(&format!("{}end\n", indent));
+    }
+}
+
+impl Exporter for MermaidExporter {
+    fn export(&self, graph: &ViewGraph) -> String {
+        let mut out = format!("flowchart {:?}\n", self.0.direction);
+        if self.0.group {
+            let tree = GroupTree::new(graph);
+            for node in &tree.top_nodes {
+                out.push_str(&format!("{}{}\n", node.id, mermaid_label(&node.name)));
+            }
+            let mut next_id = 0;
+            for group in &tree.top_groups {
+                Self::push_subgraph(&tree, group, 0, &mut next_id, &mut out);
+            }
+        } else {
+            for node in &graph.nodes {
+                out.push_str(&format!("{}{}\n", node.id, mermaid_label(&node.name)));
+            }
+        }
+        let mut cycle_edges = Vec::new();
+        for (i, edge) in graph.edges.iter().enumerate() {
+            if edge.count > 1 {
+                out.push_str(&format!("{} -->|{}| {}\n", edge.from, edge.count, edge.to));
+            } else {
+                out.push_str(&format!("{} --> {}\n", edge.from, edge.to));
+            }
+            if edge.in_cycle {
+                cycle_edges.push(i.to_string());
+            }
+        }
+        if !cycle_edges.is_empty() {
+            out.push_str(&format!(
+                "linkStyle {} stroke:#e53935,stroke-width:3px\n",
+                cycle_edges.join(",")
+            ));
+        }
+        // 节点点击后在编辑器中打开
+        for node in &graph.nodes {
+            if let Some(url) = &node.open_url {
+                out.push_str(&format!(
+                    "click {} href \"{}\"\n",
+                    node.id,
+                    url.replace('"', "%22")
+                ));
+            }
+        }
+        out
+    }
+}
+
+/// dot 中的 id 和属性值统一加引号转义
+fn quoted(value: &str) -> Id {
+    Id::Escaped(format!(
+        "\"{}\"",
+        value.replace('\\', "\\\\").replace('"', "\\\"")
+    ))
+}
+
+fn attr(key: &str, value: &str) -> Attribute {
+    Attribute(Id::Plain(key.to_owned()), quoted(value))
+}
+
+/// Graphviz dot, 每个文件一个 cluster
+pub struct DotExporter(pub ExportOptions);
+
+impl Exporter for DotExporter {
+    fn export(&self, graph: &ViewGraph) -> String {
+        let rankdir = match self.0.direction {
+            FlowDirection::TD => "TB",
+            FlowDirection::LR => "LR",
+        };
+        let mut stmts = vec![
+            Stmt::GAttribute(GraphAttributes::Graph(vec![attr("rankdir", rankdir)])),
+            Stmt::GAttribute(GraphAttributes::Node(vec![
+                attr("shape", "box"),
+                attr("style", "rounded"),
+            ])),
+        ];
+        let node_stmt = |node: &ViewNode| {
+            Stmt::Node(Node::new(
+                NodeId(quoted(&node.id), None),
+                vec![attr("label", &node.name)],
+            ))
+        };
+        let tree = GroupTree::new(graph);
+        stmts.extend(tree.top_nodes.iter().map(|node| node_stmt(node)));
+        for (i, file) in tree.top_groups.iter().enumerate() {
+            let mut cluster = vec![Stmt::Attribute(attr("label", &file.name))];
+            cluster.extend(tree.all_nodes(&file.id).into_iter().map(node_stmt));
+            stmts.push(Stmt::Subgraph(Subgraph {
+                id: Id::Plain(format!("cluster_{}", i)),
+                stmts: cluster,
+            }));
+        }
+        for edge in &graph.edges {
+            let mut attributes = Vec::new();
+            if edge.count > 1 {
+                attributes.push(attr("label", &edge.count.to_string()));
+            }
+            if edge.in_cycle {
+                attributes.push(attr("color", "#e53935"));
+                attributes.push(attr("penwidth", "3"));
+            }
+            stmts.push(Stmt::Edge(Edge {
+                ty: EdgeTy::Pair(
+                    Vertex::N(NodeId(quoted(&edge.from), None)),
+                    Vertex::N(NodeId(quoted(&edge.to), None)),
+                ),
+                attributes,
+            }));
+        }
+        Graph::DiGraph {
+            id: quoted(&graph.name),
+            strict: false,
+            stmts,
+        }
+        .print(&mut PrinterContext::default())
+    }
+}
+
+/// 节点 id 可能包含特殊字符, PlantUML 和 D2 中使用按下标生成的别名
+fn node_aliases(graph: &ViewGraph) -> HashMap<&str, String> {
+    graph
+        .nodes
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (node.id.as_str(), format!("n{}", i)))
+        .collect()
+}
+
+fn escape_quoted(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+pub struct PlantUmlExporter(pub ExportOptions);
+
+impl PlantUmlExporter {
+    fn push_package(
+        tree: &GroupTree,
+        group: &ViewGroup,
+        aliases: &HashMap<&str, String>,
+        depth: usize,
+        out: &mut String,
+    ) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!(
+            "{}package \"{}\" {{\n",
+            indent,
+            escape_quoted(&group.name)
+        ));
+        for node in tree.nodes(&group.id) {
+            out.push_str(&format!(
+                "{}  rectangle \"{}\" as {}\n",
+                indent,
+                escape_quoted(&node.name),
+                aliases[node.id.as_str()]
+            ));
+        }
+        for sub in tree.sub_groups(&group.id) {
+            Self::push_package(tree, sub, aliases, depth + 1, out);
+        }
+        out.push_str(&format!("{}}}\n", indent));
+    }
+}
+
+impl Exporter for PlantUmlExporter {
+    fn export(&self, graph: &ViewGraph) -> String {
+        let aliases = node_aliases(graph);
+        let mut out = String::from("@startuml\n");
+        if self.0.direction == FlowDirection::LR {
+            out.push_str("left to right direction\n");
+        }
+        let tree = GroupTree::new(graph);
+        let top_nodes = if self.0.group {
+            tree.top_nodes.clone()
+        } else {
+            graph.nodes.iter().collect()
+        };
+        for node in top_nodes {
+            out.push_str(&format!(
+                "rectangle \"{}\" as {}\n",
+                escape_quoted(&node.name),
+                aliases[node.id.as_str()]
+            ));
+        }
+        if self.0.group {
+            for group in &tree.top_groups {
+                Self::push_package(&tree, group, &aliases, 0, &mut out);
+            }
+        }
+        for edge in &graph.edges {
+            let arrow = if edge.in_cycle {
+                "-[#e53935,bold]->"
+            } else {
+                "-->"
+            };
+            out.push_str(&format!(
+                "{} {} {}",
+                aliases[edge.from.as_str()],
+                arrow,
+                aliases[edge.to.as_str()]
+            ));
+            if edge.count > 1 {
+                out.push_str(&format!(" : {}", edge.count));
+            }
+            out.push('\n');
+        }
+        out.push_str("@enduml\n");
+        out
+    }
+}
+
+/// D2 中嵌套的节点需要用 `分组.节点` 的完整路径引用
+pub struct D2Exporter(pub ExportOptions);
+
+impl D2Exporter {
+    fn push_container(
+        tree: &GroupTree,
+        group: &ViewGroup,
+        path: &str,
+        aliases: &HashMap<&str, String>,
+        paths: &mut HashMap<String, String>,
+        depth: usize,
+        out: &mut String,
+    ) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!(
+            "{}{}: \"{}\" {{\n",
+            indent,
+            path.rsplit('.').next().unwrap_or(path),
+            escape_quoted(&group.name)
+        ));
+        for node in tree.nodes(&group.id) {
+            let alias = &aliases[node.id.as_str()];
+            out.push_str(&format!(
+                "{}  {}: \"{}\"\n",
+                indent,
+                alias,
+                escape_quoted(&node.name)
+            ));
+            paths.insert(node.id.clone(), format!("{}.{}", path, alias));
+        }
+        for (i, sub) in tree.sub_groups(&group.id).iter().enumerate() {
+            let sub_path = format!("{}.g{}", path, i);
+            Self::push_container(tree, sub, &sub_path, aliases, paths, depth + 1, out);
+        }
+        out.push_str(&format!("{}}}\n", indent));
+    }
+}
+
+impl Exporter for D2Exporter {
+    fn export(&self, graph: &ViewGraph) -> String {
+        let aliases = node_aliases(graph);
+        let direction = match self.0.direction {
+            FlowDirection::TD => "down",
+            FlowDirection::LR => "right",
+        };
+        let mut out = format!("direction: {}\n", direction);
+        // node id -> 在 D2 中的完整路径
+        let mut paths: HashMap<String, String> = HashMap::new();
+        let tree = GroupTree::new(graph);
+        let top_nodes = if self.0.group {
+            tree.top_nodes.clone()
+        } else {
+            graph.nodes.iter().collect()
+        };
+        for node in top_nodes {
+            let alias = &aliases[node.id.as_str()];
+            out.push_str(&format!("{}: \"{}\"\n", alias, escape_quoted(&node.name)));
+            paths.insert(node.id.clone(), alias.clone());
+        }
+        if self.0.group {
+            for (i, group) in tree.top_groups.iter().enumerate() {
+                let path = format!("g{}", i);
+                Self::push_container(&tree, group, &path, &aliases, &mut paths, 0, &mut out);
+            }
+        }
+        for edge in &graph.edges {
+            // 分组缺失时节点不会输出, 连向它的边也跳过
+            let (Some(from), Some(to)) = (paths.get(&edge.from), paths.get(&edge.to)) else {
+                continue;
+            };
+            out.push_str(&format!("{} -> {}", from, to));
+            let label = if edge.count > 1 {
+                format!(" {}", edge.count)
+            } else {
+                String::new()
+            };
+            if edge.in_cycle {
+                out.push_str(&format!(
+                    ":{} {{style.stroke: \"#e53935\"; style.stroke-width: 3}}",
+                    label
+                ));
+            } else if !label.is_empty() {
+                out.push_str(&format!(":{}", label));
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
+
+/// GraphML, 分组输出为包含嵌套子图的节点
+pub struct GraphMlExporter;
+
+impl GraphMlExporter {
+    fn push_group(tree: &GroupTree, group: &ViewGroup, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        out.push_str(&format!(
+            "{}<node id=\"{}\">\n{}  <data key=\"label\">{}</data>\n{}  <graph id=\"{}:\" edgedefault=\"directed\">\n",
+            indent,
+            escape_xml(&group.id),
+            indent,
+            escape_xml(&group.name),
+            indent,
+            escape_xml(&group.id)
+        ));
+        for node in tree.nodes(&group.id) {
+            Self::push_node(node, depth + 2, out);
+        }
+        for sub in tree.sub_groups(&group.id) {
+            Self::push_group(tree, sub, depth + 2, out);
+        }
+        out.push_str(&format!("{}  </graph>\n{}</node>\n", indent, indent));
+    }
+
+    fn push_node(node: &ViewNode, depth: usize, out: &mut String) {
+        out.push_str(&format!(
+            "{}<node id=\"{}\"><data key=\"label\">{}</data></node>\n",
+            "  ".repeat(depth),
+            escape_xml(&node.id),
+            escape_xml(&node.name)
+        ));
+    }
+}
+
+impl Exporter for GraphMlExporter {
+    fn export(&self, graph: &ViewGraph) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n",
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+            "  <key id=\"count\" for=\"edge\" attr.name=\"count\" attr.type=\"int\"/>\n",
+            "  <key id=\"in_cycle\" for=\"edge\" attr.name=\"in_cycle\" attr.type=\"boolean\"/>\n",
+        ));
+        out.push_str(&format!(
+            "  <graph id=\"{}\" edgedefault=\"directed\">\n",
+            escape_xml(&graph.name)
+        ));
+        let tree = GroupTree::new(graph);
+        for node in &tree.top_nodes {
+            Self::push_node(node, 2, &mut out);
+        }
+        for group in &tree.top_groups {
+            Self::push_group(&tree, group, 2, &mut out);
+        }
+        for (i, edge) in graph.edges.iter().enumerate() {
+            out.push_str(&format!(
+                "    <edge id=\"e{}\" source=\"{}\" target=\"{}\"><data key=\"count\">{}</data><data key=\"in_cycle\">{}</data></edge>\n",
+                i,
+                escape_xml(&edge.from),
+                escape_xml(&edge.to),
+                edge.count,
+                edge.in_cycle
+            ));
+        }
+        out.push_str("  </graph>\n</graphml>\n");
+        out
+    }
+}
+
+/// GEXF, 分组输出为节点并通过 pid 表示层级
+pub struct GexfExporter;
+
+impl Exporter for GexfExporter {
+    fn export(&self, graph: &ViewGraph) -> String {
+        let mut out = String::from(concat!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+            "<gexf xmlns=\"http://www.gexf.net/1.2draft\" version=\"1.2\">\n",
+            "  <graph mode=\"static\" defaultedgetype=\"directed\">\n",
+            "    <attributes class=\"edge\">\n",
+            "      <attribute id=\"in_cycle\" title=\"in_cycle\" type=\"boolean\"/>\n",
+            "    </attributes>\n",
+            "    <nodes>\n",
+        ));
+        let push_node = |out: &mut String, id: &str, name: &str, parent: Option<&String>| {
+            let pid = parent
+                .map(|parent| format!(" pid=\"{}\"", escape_xml(parent)))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "      <node id=\"{}\" label=\"{}\"{}/>\n",
+                escape_xml(id),
+                escape_xml(name),
+                pid
+            ));
+        };
+        for group in &graph.groups {
+            push_node(&mut out, &group.id, &group.name, group.parent.as_ref());
+        }
+        for node in &graph.nodes {
+            push_node(&mut out, &node.id, &node.name, node.group.as_ref());
+        }
+        out.push_str("    </nodes>\n    <edges>\n");
+        for (i, edge) in graph.edges.iter().enumerate() {
+            out.push_str(&format!(
+                "      <edge id=\"{}\" source=\"{}\" target=\"{}\" weight=\"{}\"><attvalues><attvalue for=\"in_cycle\" value=\"{}\"/></attvalues></edge>\n",
+                i,
+                escape_xml(&edge.from),
+                escape_xml(&edge.to),
+                edge.count,
+                edge.in_cycle
+            ));
+        }
+        out.push_str("    </edges>\n  </graph>\n</gexf>\n");
+        out
+    }
+}
+
+fn node_data(node: &ViewNode, group: bool) -> ElementNodeData {
+    ElementNodeData {
+        id: node.id.clone(),
+        label: node.name.clone(),
+        group: node.group.clone(),
+        parent: node.group.clone().filter(|_| group),
+        is_group: false,
+        meta: node.meta.clone(),
+        annotation: node.annotation.clone(),
+        open_url: node.open_url.clone(),
+    }
+}
+
+fn group_data(group: &ViewGroup) -> ElementNodeData {
+    ElementNodeData {
+        id: group.id.clone(),
+        label: group.name.clone(),
+        group: group.parent.clone(),
+        parent: group.parent.clone(),
+        is_group: true,
+        ..Default::default()
+    }
+}
+
+fn edge_data(i: usize, edge: &ViewEdge) -> ElementEdgeData {
+    ElementEdgeData {
+        id: format!("e{}", i),
+        source: edge.from.clone(),
+        target: edge.to.clone(),
+        label: if edge.count > 1 {
+            edge.count.to_string()
+        } else {
+            String::new()
+        },
+        count: edge.count,
+        in_cycle: edge.in_cycle,
+    }
+}
+
+/// 节点类型与前端的 `customNodeType`/`customEdgeType` 一致.
+/// 未经服务端布局时位置只是占位, 由前端 dagre 布局; 分组内节点的位置相对于父分组
+pub fn react_flow(graph: &ViewGraph, options: ExportOptions) -> ReactFlowGraph {
+    let group_position: HashMap<&str, Position> = graph
+        .groups
+        .iter()
+        .filter_map(|group| Some((group.id.as_str(), group.position?)))
+        .collect();
+    let relative = |position: Position, parent: Option<&String>| match parent
+        .and_then(|parent| group_position.get(parent.as_str()))
+    {
+        Some(origin) => Position {
+            x: position.x - origin.x,
+            y: position.y - origin.y,
+        },
+        None => position,
+    };
+    let mut nodes = Vec::new();
+    if options.group {
+        // 父节点需要排在子节点之前, 分组按 id 排序, 子容器 id 以父容器 id 为前缀
+        for group in &graph.groups {
+            nodes.push(ReactFlowNode {
+                id: group.id.clone(),
+                node_type: "group".to_owned(),
+                position: group
+                    .position
+                    .map(|position| relative(position, group.parent.as_ref()))
+                    .unwrap_or_default(),
+                data: group_data(group),
+                parent_id: group.parent.clone(),
+                style: group.size,
+            });
+        }
+    }
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let data = node_data(node, options.group);
+        let position = match node.position {
+            Some(position) => relative(position, data.parent.as_ref()),
+            None => Position {
+                x: i as f64 * 200.0,
+                y: i as f64 * 200.0,
+            },
+        };
+        nodes.push(ReactFlowNode {
+            id: node.id.clone(),
+            node_type: "customNodeType".to_owned(),
+            position,
+            parent_id: data.parent.clone(),
+            data,
+            ..Default::default()
+        });
+    }
+    let edges = graph
+        .edges
+        .iter()
+        .enumerate()
+        .map(|(i, edge)| {
+            let data = edge_data(i, edge);
+            ReactFlowEdge {
+                id: data.id.clone(),
+                source: data.source.clone(),
+                target: data.target.clone(),
+                edge_type: "customEdgeType".to_owned(),
+                label: data.label.clone(),
+                animated: edge.in_cycle,
+                data,
+            }
+        })
+        .collect();
+    ReactFlowGraph { nodes, edges }
+}
+
+pub fn cytoscape(graph: &ViewGraph, options: ExportOptions) -> CytoscapeElements {
+    let mut nodes = Vec::new();
+    if options.group {
+        for group in &graph.groups {
+            nodes.push(CytoscapeNode {
+                data: group_data(group),
+                position: None,
+            });
+        }
+    }
+    // Cytoscape.js 的位置为节点中心, 分组的外框由其子节点决定
+    for node in &graph.nodes {
+        nodes.push(CytoscapeNode {
+            data: node_data(node, options.group),
+            position: node.position.map(|position| Position {
+                x: position.x + layout::NODE_WIDTH / 2.0,
+                y: position.y + layout::NODE_HEIGHT / 2.0,
+            }),
+        });
+    }
+    let edges = graph
+        .edges
+        .iter()
+        .enumerate()
+        .map(|(i, edge)| CytoscapeEdge {
+            data: edge_data(i, edge),
+        })
+        .collect();
+    CytoscapeElements { nodes, edges }
+}
+
+/// 调用本地安装的 graphviz `dot` 渲染图片
+pub fn render(dot: String, engine: LayoutEngine, format: Format) -> Result<Vec<u8>> {
+    let layout = match engine {
+        LayoutEngine::Dot => Layout::Dot,
+        LayoutEngine::Neato => Layout::Neato,
+        LayoutEngine::Fdp => Layout::Fdp,
+    };
+    graphviz_rust::exec_dot(
+        dot,
+        vec![CommandArg::Layout(layout), CommandArg::Format(format)],
+    )
+    .map_err(|e| match e.kind() {
+        std::io::ErrorKind::NotFound => anyhow::anyhow!(
+            "graphviz `dot` executable not found, install graphviz and make sure `dot` is in PATH"
+        ),
+        _ => anyhow::anyhow!("graphviz render failed: {}", e),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 两个文件, lib.rs 中有一个 impl, ping/new 互相调用
+    fn sample() -> (GraphData, ViewState) {
+        let mut data = GraphData::default();
+        let nodes = [
+            ("1", "src/main.rs:", None),
+            ("2", "src/lib.rs:", None),
+            ("2:4_5>", ":src/lib.rs::impl Foo:", Some("2")),
+            ("1:0_3", ":src/main.rs:main:", Some("1")),
+            ("1:5_3", ":src/main.rs:helper:", Some("1")),
+            ("2:0_7", ":src/lib.rs:util:", Some("2")),
+            ("2:5_11", ":src/lib.rs::impl Foo:new:", Some("2:4_5>")),
+            ("2:8_11", ":src/lib.rs::impl Foo:ping:", Some("2:4_5>")),
+        ];
+        for (id, name, parent) in nodes {
+            data.node_id_to_name.insert(id.to_owned(), name.to_owned());
+            if let Some(parent) = parent {
+                data.node_parent.insert(id.to_owned(), parent.to_owned());
+            }
+        }
+        let edges = [
+            ("1:0_3", "1:5_3"),
+            ("1:0_3", "2:5_11"),
+            ("1:5_3", "2:0_7"),
+            ("2:5_11", "2:8_11"),
+            ("2:8_11", "2:5_11"),
+            ("2:8_11", "2:0_7"),
+        ];
+        for (from, to) in edges {
+            data.edge_from_to
+                .entry(from.to_owned())
+                .or_default()
+                .insert(to.to_owned());
+            data.edge_to_from
+                .entry(to.to_owned())
+                .or_default()
+                .insert(from.to_owned());
+        }
+        let view = ViewState {
+            node_set: ["1:0_3", "1:5_3", "2:0_7", "2:5_11", "2:8_11"]
+                .into_iter()
+                .map(str::to_owned)
+                .collect(),
+            ..Default::default()
+        };
+        (data, view)
+    }
+
+    #[test]
+    fn same_view_gives_same_output() {
+        let (data, view) = sample();
+        let (other_data, other_view) = sample();
+        let first = view_graph(&data, &view, true);
+        let second = view_graph(&other_data, &other_view, true);
+        assert_eq!(
+            serde_json::to_string(&first).unwrap(),
+            serde_json::to_string(&second).unwrap()
+        );
+        let ids: Vec<&str> = first.nodes.iter().map(|node| node.id.as_str()).collect();
+        assert_eq!(ids, ["1:0_3", "1:5_3", "2:0_7", "2:5_11", "2:8_11"]);
+        let options = ExportOptions {
+            group: true,
+            ..Default::default()
+        };
+        for format in [ExportFormat::Mermaid, ExportFormat::Dot] {
+            assert_eq!(
+                exporter(format, options).export(&first),
+                exporter(format, options).export(&second)
+            );
+        }
+    }
+
+    #[test]
+    fn collapsed_container_gets_safe_id_and_display_name() {
+        let (data, mut view) = sample();
+        view.collapsed.insert("2:4_5>".to_owned());
+        let graph = view_graph(&data, &view, false);
+        let collapsed = graph.nodes.iter().find(|node| node.id == "c0").unwrap();
+        assert_eq!(collapsed.name, "impl Foo (2)");
+        assert_eq!(collapsed.group.as_deref(), Some("2"));
+        let mermaid = MermaidExporter(ExportOptions::default()).export(&graph);
+        assert_eq!(
+            mermaid,
+            "flowchart TD\n\
+             1:0_3[\":src/main.rs:main:\"]\n\
+             1:5_3[\":src/main.rs:helper:\"]\n\
+             2:0_7[\":src/lib.rs:util:\"]\n\
+             c0[\"impl Foo (2)\"]\n\
+             1:0_3 --> 1:5_3\n\
+             1:0_3 --> c0\n\
+             1:5_3 --> 2:0_7\n\
+             c0 --> 2:0_7\n"
+        );
+    }
+
+    /// 除 mermaid/dot 以外的文本格式
+    fn text_exporters(options: ExportOptions) -> Vec<(&'static str, Box<dyn Exporter>)> {
+        vec![
+            ("plantuml", Box::new(PlantUmlExporter(options))),
+            ("d2", Box::new(D2Exporter(options))),
+            ("graphml", Box::new(GraphMlExporter)),
+            ("gexf", Box::new(GexfExporter)),
+        ]
+    }
+
+    #[test]
+    fn exporters_escape_names() {
+        let (data, mut view) = sample();
+        view.node_id_to_new_name
+            .insert("1:0_3".to_owned(), "a \"b\" <c> & d".to_owned());
+        let graph = view_graph(&data, &view, false);
+        let options = ExportOptions {
+            group: true,
+            ..Default::default()
+        };
+        for (format, exporter) in text_exporters(options) {
+            let out = exporter.export(&graph);
+            let expected = match format {
+                "plantuml" | "d2" => "\"a \\\"b\\\" <c> & d\"",
+                _ => "a &quot;b&quot; &lt;c&gt; &amp; d",
+            };
+            assert!(out.contains(expected), "{}:\n{}", format, out);
+        }
+    }
+
+    #[test]
+    fn exporters_handle_collapsed_view() {
+        let (data, mut view) = sample();
+        view.collapsed.insert("2:4_5>".to_owned());
+        let graph = view_graph(&data, &view, false);
+        let options = ExportOptions {
+            group: true,
+            ..Default::default()
+        };
+        for (format, exporter) in text_exporters(options) {
+            let out = exporter.export(&graph);
+            assert!(out.contains("impl Foo (2)"), "{}:\n{}", format, out);
+            assert!(!out.contains("2:4_5>"), "{}:\n{}", format, out);
+        }
+        let d2 = D2Exporter(options).export(&graph);
+        assert!(d2.contains("g0.n0 -> g1.n3\n"), "{}", d2);
+        assert!(d2.contains("g1.n3 -> g1.n2\n"), "{}", d2);
+    }
+
+    #[test]
+    fn exporters_handle_aggregated_view() {
+        let (data, view) = sample();
+        let graph = aggregated_view_graph(&data, &view, AggregateLevel::File, false);
+        for (format, exporter) in text_exporters(ExportOptions::default()) {
+            let out = exporter.export(&graph);
+            for node in &graph.nodes {
+                assert!(out.contains(&node.name), "{}:\n{}", format, out);
+            }
+        }
+        let d2 = D2Exporter(ExportOptions::default()).export(&graph);
+        assert!(d2.contains("n0 -> n1"), "{}", d2);
+    }
+
+    #[test]
+    fn aggregated_view_highlights_cycles_between_groups() {
+        let (mut data, view) = sample();
+        data.edge_from_to
+            .entry("2:0_7".to_owned())
+            .or_default()
+            .insert("1:5_3".to_owned());
+        let in_cycle = |highlight_cycles| {
+            aggregated_view_graph(&data, &view, AggregateLevel::File, highlight_cycles)
+                .edges
+                .iter()
+                .map(|edge| edge.in_cycle)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(in_cycle(true), [true, true]);
+        assert_eq!(in_cycle(false), [false, false]);
+    }
+
+    #[test]
+    fn d2_skips_edges_to_missing_nodes() {
+        let graph = ViewGraph {
+            nodes: vec![
+                ViewNode {
+                    id: "a".to_owned(),
+                    name: "a".to_owned(),
+                    ..Default::default()
+                },
+                ViewNode {
+                    id: "b".to_owned(),
+                    name: "b".to_owned(),
+                    group: Some("missing".to_owned()),
+                    ..Default::default()
+                },
+            ],
+            edges: vec![ViewEdge {
+                from: "a".to_owned(),
+                to: "b".to_owned(),
+                count: 1,
+                in_cycle: false,
+            }],
+            ..Default::default()
+        };
+        let options = ExportOptions {
+            group: true,
+            ..Default::default()
+        };
+        let out = D2Exporter(options).export(&graph);
+        assert_eq!(out, "direction: down\nn0: \"a\"\n");
+    }
+}
@@ -0,0 +1,680 @@
+use crate::api_model::{FlowDirection, Position, Size, ViewGraph};
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+/// 与前端 dagre 布局使用的节点宽度一致
+pub const NODE_WIDTH: f64 = 250.0;
+pub const NODE_HEIGHT: f64 = 100.0;
+/// 同一层内相邻节点的间距
+const NODE_GAP: f64 = 50.0;
+/// 相邻层之间的间距
+const LAYER_GAP: f64 = 100.0;
+/// 同一层内不同文件的节点之间额外的间距
+const CLUSTER_GAP: f64 = 100.0;
+const GROUP_PADDING: f64 = 40.0;
+/// 交叉最小化的上下扫描次数
+const SWEEPS: usize = 8;
+
+/// 分层(Sugiyama)布局: 去环, 最长路径分层, 长边插入虚拟节点, 按重心排序减少交叉, 同一文件的节点在层内保持相邻.
+/// 所有步骤按 id 排序处理, 同一视图每次得到相同的坐标
+pub fn layout(graph: &mut ViewGraph, direction: FlowDirection) {
+    let mut order: Vec<usize> = (0..graph.nodes.len()).collect();
+    order.sort_by(|a, b| graph.nodes[*a].id.cmp(&graph.nodes[*b].id));
+    let index: HashMap<&str, usize> = order
+        .iter()
+        .enumerate()
+        .map(|(i, node)| (graph.nodes[*node].id.as_str(), i))
+        .collect();
+    let (group_index, parents) = group_parents(graph);
+    // 节点所在的顶层分组(文件), 没有分组的节点自成一组
+    let mut clusters: Vec<String> = order
+        .iter()
+        .map(|node| {
+            let node = &graph.nodes[*node];
+            let group = node.group.as_ref().and_then(|group| group_index.get(group));
+            match group {
+                Some(g) => {
+                    let mut top = *g;
+                    while let Some(parent) = parents[top] {
+                        top = parent;
+                    }
+                    graph.groups[top].id.clone()
+                }
+                None => node.id.clone(),
+            }
+        })
+        .collect();
+    let mut edges = BTreeSet::new();
+    for edge in &graph.edges {
+        if let (Some(from), Some(to)) = (index.get(edge.from.as_str()), index.get(edge.to.as_str()))
+        {
+            if from != to {
+                edges.insert((*from, *to));
+            }
+        }
+    }
+    let n = order.len();
+    let edges = remove_cycles(n, &edges);
+    let layer = assign_layers(n, &edges);
+
+    // 跨越多层的边拆成经过虚拟节点的短边
+    let mut layer = layer;
+    let mut succs: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (from, to) in edges {
+        let mut current = from;
+        for l in layer[from] + 1..layer[to] {
+            let dummy = layer.len();
+            layer.push(l);
+            succs.push(Vec::new());
+            let cluster = if clusters[from] == clusters[to] {
+                clusters[from].clone()
+            } else {
+                format!("\u{0}{}", dummy)
+            };
+            clusters.push(cluster);
+            succs[current].push(dummy);
+            current = dummy;
+        }
+        succs[current].push(to);
+    }
+    let total = layer.len();
+    let mut preds: Vec<Vec<usize>> = vec![Vec::new(); total];
+    for (from, tos) in succs.iter().enumerate() {
+        for to in tos {
+            preds[*to].push(from);
+        }
+    }
+    let layer_count = layer.iter().max().map(|l| l + 1).unwrap_or(0);
+    let mut layers: Vec<Vec<usize>> = vec![Vec::new(); layer_count];
+    for (v, l) in layer.iter().enumerate() {
+        layers[*l].push(v);
+    }
+    for vertices in layers.iter_mut() {
+        vertices.sort_by(|a, b| clusters[*a].cmp(&clusters[*b]).then(a.cmp(b)));
+    }
+
+    let mut best = layers.clone();
+    let mut best_crossings = count_crossings(&layers, &succs);
+    for sweep in 0..SWEEPS {
+        if sweep % 2 == 0 {
+            for l in 1..layer_count {
+                let (upper, lower) = layers.split_at_mut(l);
+                reorder(&mut lower[0], &upper[l - 1], &preds, &clusters);
+            }
+        } else {
+            for l in (0..layer_count.saturating_sub(1)).rev() {
+                let (upper, lower) = layers.split_at_mut(l + 1);
+                reorder(&mut upper[l], &lower[0], &succs, &clusters);
+            }
+        }
+        let crossings = count_crossings(&layers, &succs);
+        if crossings < best_crossings {
+            best_crossings = crossings;
+            best = layers.clone();
+        }
+    }
+
+    // 虚拟节点只参与排序, 坐标按分组划分的条带分配, 同一分组在各层中占据相同的范围, 分组外框互不重叠
+    let root = graph.groups.len();
+    let mut bands = Bands {
+        layer: &layer,
+        rank: vec![0.0; n],
+        members: vec![Vec::new(); root + 1],
+        children: vec![Vec::new(); root + 1],
+        weight: vec![(0.0, 0); root + 1],
+        layer_count,
+        node_step: 0.0,
+        node_size: 0.0,
+        along: vec![0.0; n],
+    };
+    for vertices in &best {
+        for (i, v) in vertices.iter().enumerate().filter(|(_, v)| **v < n) {
+            bands.rank[*v] = i as f64;
+        }
+    }
+    for (g, parent) in parents.iter().enumerate() {
+        bands.children[parent.unwrap_or(root)].push(g);
+    }
+    for (i, node) in order.iter().enumerate() {
+        let group = graph.nodes[*node]
+            .group
+            .as_ref()
+            .and_then(|group| group_index.get(group).copied());
+        bands.members[group.unwrap_or(root)].push(i);
+        let mut ancestor = group;
+        while let Some(g) = ancestor {
+            bands.weight[g].0 += bands.rank[i];
+            bands.weight[g].1 += 1;
+            ancestor = parents[g];
+        }
+    }
+    for members in bands.members.iter_mut() {
+        members.sort_by(|a, b| bands.rank[*a].total_cmp(&bands.rank[*b]));
+    }
+    (bands.node_size, bands.node_step) = match direction {
+        FlowDirection::TD => (NODE_WIDTH, NODE_WIDTH + NODE_GAP),
+        FlowDirection::LR => (NODE_HEIGHT, NODE_HEIGHT + NODE_GAP),
+    };
+    let layer_step = match direction {
+        FlowDirection::TD => NODE_HEIGHT + LAYER_GAP,
+        FlowDirection::LR => NODE_WIDTH + LAYER_GAP,
+    };
+    bands.place(root, 0.0);
+    for (i, node) in order.iter().enumerate() {
+        let (along, across) = (bands.along[i], layer[i] as f64 * layer_step);
+        graph.nodes[*node].position = Some(match direction {
+            FlowDirection::TD => Position {
+                x: along,
+                y: across,
+            },
+            FlowDirection::LR => Position {
+                x: across,
+                y: along,
+            },
+        });
+    }
+    layout_groups(graph);
+}
+
+/// 按 id 顺序深度优先遍历, 反转指向栈中节点的回边, 丢弃自环
+fn remove_cycles(n: usize, edges: &BTreeSet<(usize, usize)>) -> Vec<(usize, usize)> {
+    let mut succs: Vec<Vec<usize>> = vec![Vec::new(); n];
+    for (from, to) in edges {
+        succs[*from].push(*to);
+    }
+    // 0 未访问, 1 在栈中, 2 已完成
+    let mut state = vec![0u8; n];
+    let mut reversed = BTreeSet::new();
+    for root in 0..n {
+        if state[root] != 0 {
+            continue;
+        }
+        state[root] = 1;
+        let mut stack = vec![(root, 0)];
+        while let Some((v, i)) = stack.last_mut() {
+            let v = *v;
+            if *i < succs[v].len() {
+                let to = succs[v][*i];
+                *i += 1;
+                match state[to] {
+                    0 => {
+                        state[to] = 1;
+                        stack.push((to, 0));
+                    }
+                    1 => {
+                        reversed.insert((v, to));
+                    }
+                    _ => {}
+                }
+            } else {
+                state[v] = 2;
+                stack.pop();
+            }
+        }
+    }
+    let mut dag = BTreeSet::new();
+    for (from, to) in edges.iter().filter(|(from, to)| from != to) {
+        if reversed.contains(&(*from, *to)) {
+            dag.insert((*to, *from));
+        } else {
+            dag.insert((*from, *to));
+        }
+    }
+    dag.into_iter().collect()
+}
+
+/// 最长路径分层, 没有入边的节点在第 0 层
+fn assign_layers(n: usize, edges: &[(usize, usize)]) -> Vec<usize> {
+    let mut succs: Vec<Vec<usize>> = vec![Vec::new(); n];
+    let mut in_degree = vec![0; n];
+    for (from, to) in edges {
+        succs[*from].push(*to);
+        in_degree[*to] += 1;
+    }
+    let mut layer = vec![0; n];
+    let mut queue: VecDeque<usize> = (0..n).filter(|v| in_degree[*v] == 0).collect();
+    while let Some(v) = queue.pop_front() {
+        for to in &succs[v] {
+            layer[*to] = layer[*to].max(layer[v] + 1);
+            in_degree[*to] -= 1;
+            if in_degree[*to] == 0 {
+                queue.push_back(*to);
+            }
+        }
+    }
+    layer
+}
+
+/// 按相邻层中邻居的平均位置重新排序, 同一文件的节点按文件的平均重心整体移动
+fn reorder(
+    vertices: &mut [usize],
+    fixed: &[usize],
+    neighbours: &[Vec<usize>],
+    clusters: &[String],
+) {
+    let fixed_pos: HashMap<usize, usize> = fixed.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+    let mut barycenter = HashMap::new();
+    for (i, v) in vertices.iter().enumerate() {
+        let positions: Vec<usize> = neighbours[*v]
+            .iter()
+            .filter_map(|u| fixed_pos.get(u).copied())
+            .collect();
+        let value = if positions.is_empty() {
+            i as f64
+        } else {
+            positions.iter().sum::<usize>() as f64 / positions.len() as f64
+        };
+        barycenter.insert(*v, value);
+    }
+    let mut cluster_sum: HashMap<&str, (f64, usize)> = HashMap::new();
+    for v in vertices.iter() {
+        let entry = cluster_sum.entry(clusters[*v].as_str()).or_default();
+        entry.0 += barycenter[v];
+        entry.1 += 1;
+    }
+    let cluster_barycenter = |v: usize| {
+        let (sum, count) = cluster_sum[clusters[v].as_str()];
+        sum / count as f64
+    };
+    let current: HashMap<usize, usize> =
+        vertices.iter().enumerate().map(|(i, v)| (*v, i)).collect();
+    vertices.sort_by(|a, b| {
+        cluster_barycenter(*a)
+            .total_cmp(&cluster_barycenter(*b))
+            .then_with(|| clusters[*a].cmp(&clusters[*b]))
+            .then_with(|| barycenter[a].total_cmp(&barycenter[b]))
+            .then_with(|| current[a].cmp(&current[b]))
+    });
+}
+
+/// 相邻层之间边的交叉数, 即按上层位置排序后下层位置的逆序对数
+fn count_crossings(layers: &[Vec<usize>], succs: &[Vec<usize>]) -> usize {
+    let mut total = 0;
+    for pair in layers.windows(2) {
+        let upper: HashMap<usize, usize> =
+            pair[0].iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        let lower: HashMap<usize, usize> =
+            pair[1].iter().enumerate().map(|(i, v)| (*v, i)).collect();
+        let mut edges: Vec<(usize, usize)> = Vec::new();
+        for v in &pair[0] {
+            for to in &succs[*v] {
+                if let Some(to) = lower.get(to) {
+                    edges.push((upper[v], *to));
+                }
+            }
+        }
+        edges.sort();
+        // 树状数组统计逆序对
+        let size = pair[1].len() + 1;
+        let mut tree = vec![0usize; size + 1];
+        for (seen, (_, to)) in edges.iter().enumerate() {
+            let mut not_greater = 0;
+            let mut i = to + 1;
+            while i > 0 {
+                not_greater += tree[i];
+                i -= i & i.wrapping_neg();
+            }
+            total += seen - not_greater;
+            let mut i = to + 1;
+            while i <= size {
+                tree[i] += 1;
+                i += i & i.wrapping_neg();
+            }
+        }
+    }
+    total
+}
+
+/// 分组 id -> 下标, 以及每个分组的父分组下标, 保证父子关系无环
+fn group_parents(graph: &ViewGraph) -> (HashMap<String, usize>, Vec<Option<usize>>) {
+    let group_index: HashMap<String, usize> = graph
+        .groups
+        .iter()
+        .enumerate()
+        .map(|(i, group)| (group.id.clone(), i))
+        .collect();
+    let mut parents: Vec<Option<usize>> = graph
+        .groups
+        .iter()
+        .map(|group| {
+            group
+                .parent
+                .as_ref()
+                .and_then(|parent| group_index.get(parent).copied())
+        })
+        .collect();
+    // 循环的父子关系当作没有父分组处理
+    for g in 0..parents.len() {
+        let mut current = g;
+        for _ in 0..=parents.len() {
+            match parents[current] {
+                Some(parent) => current = parent,
+                None => break,
+            }
+        }
+        if parents[current].is_some() {
+            parents[g] = None;
+        }
+    }
+    (group_index, parents)
+}
+
+/// 沿层方向的坐标分配, 下标 `groups.len()` 表示不属于任何分组的根
+struct Bands<'a> {
+    layer: &'a [usize],
+    /// 节点在所在层中的次序
+    rank: Vec<f64>,
+    /// 直接属于分组的节点, 按次序排列
+    members: Vec<Vec<usize>>,
+    children: Vec<Vec<usize>>,
+    /// 分组内所有节点的次序之和与节点数
+    weight: Vec<(f64, usize)>,
+    layer_count: usize,
+    node_step: f64,
+    node_size: f64,
+    along: Vec<f64>,
+}
+
+impl Bands<'_> {
+    /// 分组的直属节点和子分组按平均次序并排放置, 返回占用的宽度
+    fn place(&mut self, group: usize, start: f64) -> f64 {
+        let members = std::mem::take(&mut self.members[group]);
+        // None 表示直属节点
+        let mut items: Vec<(f64, Option<usize>)> = self.children[group]
+            .iter()
+            .map(|g| {
+                let (sum, count) = self.weight[*g];
+                (sum / count.max(1) as f64, Some(*g))
+            })
+            .collect();
+        if !members.is_empty() {
+            let sum: f64 = members.iter().map(|v| self.rank[*v]).sum();
+            items.push((sum / members.len() as f64, None));
+        }
+        items.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let mut cursor = start;
+        for (i, (_, item)) in items.into_iter().enumerate() {
+            if i > 0 {
+                cursor += NODE_GAP + CLUSTER_GAP;
+            }
+            cursor += match item {
+                Some(g) => GROUP_PADDING * 2.0 + self.place(g, cursor + GROUP_PADDING),
+                None => self.place_members(&members, cursor),
+            };
+        }
+        cursor - start
+    }
+
+    /// 每层的节点在条带内居中
+    fn place_members(&mut self, members: &[usize], start: f64) -> f64 {
+        let mut layers: Vec<Vec<usize>> = vec![Vec::new(); self.layer_count];
+        for v in members {
+            layers[self.layer[*v]].push(*v);
+        }
+        let slots = layers.iter().map(|l| l.len()).max().unwrap_or(0);
+        for vertices in layers {
+            let shift = (slots - vertices.len()) as f64 * self.node_step / 2.0;
+            for (i, v) in vertices.into_iter().enumerate() {
+                self.along[v] = start + shift + i as f64 * self.node_step;
+            }
+        }
+        (slots - 1) as f64 * self.node_step + self.node_size
+    }
+}
+
+/// 分组的位置和大小为其中节点和子分组的外框加上内边距
+fn layout_groups(graph: &mut ViewGraph) {
+    let (group_index, parents) = group_parents(graph);
+    let depth = |mut i: usize| {
+        let mut depth = 0;
+        while let Some(parent) = parents[i] {
+            depth += 1;
+            i = parent;
+        }
+        depth
+    };
+    // (min_x, min_y, max_x, max_y)
+    let mut bounds: Vec<Option<(f64, f64, f64, f64)>> = vec![None; graph.groups.len()];
+    let extend = |bound: &mut Option<(f64, f64, f64, f64)>, rect: (f64, f64, f64, f64)| {
+        *bound = Some(match *bound {
+            Some(b) => (
+                b.0.min(rect.0),
+                b.1.min(rect.1),
+                b.2.max(rect.2),
+                b.3.max(rect.3),
+            ),
+            None => rect,
+        });
+    };
+    for node in &graph.nodes {
+        let (Some(group), Some(position)) = (&node.group, node.position) else {
+            continue;
+        };
+        if let Some(i) = group_index.get(group) {
+            let rect = (
+                position.x,
+                position.y,
+                position.x + NODE_WIDTH,
+                position.y + NODE_HEIGHT,
+            );
+            extend(&mut bounds[*i], rect);
+        }
+    }
+    // 先处理最深的分组, 再将其外框并入父分组
+    let mut groups: Vec<usize> = (0..graph.groups.len()).collect();
+    groups.sort_by_key(|i| std::cmp::Reverse(depth(*i)));
+    for i in groups {
+        let Some(b) = bounds[i] else {
+            continue;
+        };
+        let padded = (
+            b.0 - GROUP_PADDING,
+            b.1 - GROUP_PADDING,
+            b.2 + GROUP_PADDING,
+            b.3 + GROUP_PADDING,
+        );
+        bounds[i] = Some(padded);
+        if let Some(parent) = parents[i] {
+            extend(&mut bounds[parent], padded);
+        }
+    }
+    for (group, bound) in graph.groups.iter_mut().zip(bounds) {
+        if let Some(b) = bound {
+            group.position = Some(Position { x: b.0, y: b.1 });
+            group.size = Some(Size {
+                width: b.2 - b.0,
+                height: b.3 - b.1,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api_model::{ViewEdge, ViewGroup, ViewNode};
+
+    fn node(id: &str, group: Option<&str>) -> ViewNode {
+        ViewNode {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            group: group.map(str::to_owned),
+            ..Default::default()
+        }
+    }
+
+    fn edge(from: &str, to: &str) -> ViewEdge {
+        ViewEdge {
+            from: from.to_owned(),
+            to: to.to_owned(),
+            count: 1,
+            in_cycle: false,
+        }
+    }
+
+    fn group(id: &str, parent: Option<&str>) -> ViewGroup {
+        ViewGroup {
+            id: id.to_owned(),
+            name: id.to_owned(),
+            parent: parent.map(str::to_owned),
+            ..Default::default()
+        }
+    }
+
+    /// 两个文件, 其中一个文件内有嵌套的 impl, 含一个跨文件的环
+    fn sample() -> ViewGraph {
+        ViewGraph {
+            name: "test".to_owned(),
+            nodes: vec![
+                node("main", Some("f1")),
+                node("helper", Some("f1")),
+                node("new", Some("impl")),
+                node("ping", Some("impl")),
+                node("util", Some("f2")),
+                node("free", None),
+            ],
+            edges: vec![
+                edge("main", "helper"),
+                edge("main", "new"),
+                edge("helper", "ping"),
+                edge("ping", "new"),
+                edge("new", "util"),
+                edge("util", "helper"),
+                edge("main", "free"),
+            ],
+            groups: vec![
+                group("f1", None),
+                group("f2", None),
+                group("impl", Some("f2")),
+            ],
+        }
+    }
+
+    fn positions(graph: &ViewGraph) -> Vec<(String, Position)> {
+        let mut positions: Vec<(String, Position)> = graph
+            .nodes
+            .iter()
+            .map(|node| (node.id.clone(), node.position.unwrap()))
+            .collect();
+        positions.sort_by(|a, b| a.0.cmp(&b.0));
+        positions
+    }
+
+    #[test]
+    fn two_cycle_gets_valid_layering() {
+        let edges = BTreeSet::from([(0, 1), (1, 0)]);
+        let dag = remove_cycles(2, &edges);
+        assert_eq!(dag, vec![(0, 1)]);
+        let layer = assign_layers(2, &dag);
+        assert_eq!(layer, vec![0, 1]);
+
+        let mut graph = ViewGraph {
+            nodes: vec![node("a", None), node("b", None)],
+            edges: vec![edge("a", "b"), edge("b", "a")],
+            ..Default::default()
+        };
+        layout(&mut graph, FlowDirection::TD);
+        let positions = positions(&graph);
+        assert!(positions[0].1.y < positions[1].1.y);
+    }
+
+    #[test]
+    fn every_edge_points_to_a_later_layer() {
+        let edges = BTreeSet::from([(0, 1), (1, 2), (2, 0), (2, 3), (3, 3), (0, 3)]);
+        let dag = remove_cycles(4, &edges);
+        let layer = assign_layers(4, &dag);
+        for (from, to) in dag {
+            assert!(layer[from] < layer[to], "{} -> {}", from, to);
+        }
+    }
+
+    #[test]
+    fn counts_crossings() {
+        let layers = vec![vec![0, 1], vec![2, 3]];
+        let crossed = vec![vec![3], vec![2], vec![], vec![]];
+        assert_eq!(count_crossings(&layers, &crossed), 1);
+        let straight = vec![vec![2], vec![3], vec![], vec![]];
+        assert_eq!(count_crossings(&layers, &straight), 0);
+        let layers = vec![vec![0, 1, 2], vec![3, 4, 5]];
+        let reversed = vec![vec![5], vec![4], vec![3], vec![], vec![], vec![]];
+        assert_eq!(count_crossings(&layers, &reversed), 3);
+    }
+
+    #[test]
+    fn same_input_gives_same_coordinates() {
+        let mut first = sample();
+        layout(&mut first, FlowDirection::TD);
+        let mut second = sample();
+        second.nodes.reverse();
+        second.edges.reverse();
+        layout(&mut second, FlowDirection::TD);
+        assert_eq!(positions(&first), positions(&second));
+        let groups = |graph: &ViewGraph| {
+            let mut groups: Vec<_> = graph
+                .groups
+                .iter()
+                .map(|group| (group.id.clone(), group.position, group.size))
+                .collect();
+            groups.sort_by(|a, b| a.0.cmp(&b.0));
+            groups
+        };
+        assert_eq!(groups(&first), groups(&second));
+    }
+
+    #[test]
+    fn empty_graph_does_not_panic() {
+        let mut graph = ViewGraph::default();
+        layout(&mut graph, FlowDirection::TD);
+        layout(&mut graph, FlowDirection::LR);
+        assert!(graph.nodes.is_empty());
+    }
+
+    #[test]
+    fn grouped_nodes_stay_inside_their_groups() {
+        for direction in [FlowDirection::TD, FlowDirection::LR] {
+            let mut graph = sample();
+            layout(&mut graph, direction);
+            let rect = |id: &str| {
+                let group = graph.groups.iter().find(|group| group.id == id).unwrap();
+                let (position, size) = (group.position.unwrap(), group.size.unwrap());
+                (
+                    position.x,
+                    position.y,
+                    position.x + size.width,
+                    position.y + size.height,
+                )
+            };
+            let inside = |inner: (f64, f64, f64, f64), outer: (f64, f64, f64, f64)| {
+                inner.0 >= outer.0 && inner.1 >= outer.1 && inner.2 <= outer.2 && inner.3 <= outer.3
+            };
+            for node in &graph.nodes {
+                let Some(group) = &node.group else {
+                    continue;
+                };
+                let position = node.position.unwrap();
+                let bounds = (
+                    position.x,
+                    position.y,
+                    position.x + NODE_WIDTH,
+                    position.y + NODE_HEIGHT,
+                );
+                assert!(inside(bounds, rect(group)), "{} outside {}", node.id, group);
+            }
+            assert!(inside(rect("impl"), rect("f2")));
+            // 不同文件的外框不重叠
+            let (f1, f2) = (rect("f1"), rect("f2"));
+            assert!(f1.2 <= f2.0 || f2.2 <= f1.0 || f1.3 <= f2.1 || f2.3 <= f1.1);
+        }
+    }
+
+    #[test]
+    fn cyclic_groups_do_not_hang() {
+        let mut graph = ViewGraph {
+            nodes: vec![node("a", Some("g1")), node("b", Some("g2"))],
+            edges: vec![edge("a", "b")],
+            groups: vec![group("g1", Some("g2")), group("g2", Some("g1"))],
+            ..Default::default()
+        };
+        layout(&mut graph, FlowDirection::TD);
+        assert!(graph.nodes.iter().all(|node| node.position.is_some()));
+    }
+}
@@ -1,4 +1,11 @@
 pub mod dot_parse;
 pub mod model;
 pub mod api;
-pub mod api_model;
\ No newline at end of file
+pub mod api_model;
+pub mod config;
+pub mod workspace;
+pub mod analysis;
+pub mod report;
+pub mod diff;
+pub mod export;
+pub mod layout;
\ No newline at end of file
@@ -1,4 +1,3 @@
-
 #[derive(Debug, Clone, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Default)]
 pub struct Edge {
     pub from: String,
@@ -26,5 +25,32 @@ pub struct HtmlNode {
     pub text: String,
     pub id: String,
     pub children: Vec<HtmlNode>,
+    /// 所在文件, 即顶层文件节点的路径
+    #[serde(default)]
+    pub file: String,
+    /// 起始行, 从 1 开始
+    #[serde(default)]
+    pub line: Option<usize>,
+    /// 起始列, 从 1 开始
+    #[serde(default)]
+    pub column: Option<usize>,
+    /// 估算的结束行, 为空表示到文件末尾
+    #[serde(default)]
+    pub end_line: Option<usize>,
+    #[serde(default)]
+    pub kind: NodeKind,
 }
 
+/// 节点类别, 由其在 crabviz 输出中的层级推断
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize, Default,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    File,
+    Struct,
+    Impl,
+    #[default]
+    Function,
+    Method,
+}
@@ -0,0 +1,53 @@
+use crate::api_model::DeadCodeReport;
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+pub fn dead_code_csv(report: &DeadCodeReport) -> String {
+    let mut csv = String::from("file,id,name\n");
+    for file in &report.files {
+        for node in &file.nodes {
+            csv.push_str(&format!(
+                "{},{},{}\n",
+                csv_field(&file.file),
+                csv_field(&node.id),
+                csv_field(node.name.as_deref().unwrap_or_default())
+            ));
+        }
+    }
+    csv
+}
+
+pub fn dead_code_markdown(report: &DeadCodeReport) -> String {
+    let mut md = String::from("# Dead code report\n\n");
+    md.push_str(&format!(
+        "{} of {} functions are unreachable from {} entry points.\n\n",
+        report.unreachable_count,
+        report.total_functions,
+        report.entry_points.len()
+    ));
+    md.push_str("## Entry points\n\n");
+    for node in &report.entry_points {
+        md.push_str(&format!(
+            "- `{}` ({})\n",
+            node.name.as_deref().unwrap_or_default(),
+            node.id
+        ));
+    }
+    for file in &report.files {
+        md.push_str(&format!("\n## {}\n\n", file.file));
+        for node in &file.nodes {
+            md.push_str(&format!(
+                "- `{}` ({})\n",
+                node.name.as_deref().unwrap_or_default(),
+                node.id
+            ));
+        }
+    }
+    md
+}
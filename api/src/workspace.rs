@@ -0,0 +1,43 @@
+use crate::{api_model::Workspace, config::get_config, dot_parse::write_to_file};
+use anyhow::{Context, Result};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+
+/// 文件内容的 sha256, 用于判断工作区保存后源文件是否变化
+#[auto_context::auto_context]
+pub fn file_hash(path: &str) -> Result<String> {
+    let raw = std::fs::read(path)?;
+    Ok(format!("{:x}", Sha256::digest(&raw)))
+}
+
+/// 工作区名只允许字母数字和 `-` `_`, 文件保存在 workspace_dir 下
+#[auto_context::auto_context]
+pub fn workspace_path(name: &str) -> Result<PathBuf> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if !valid {
+        return Err(anyhow::anyhow!("invalid workspace name {}", name));
+    }
+    Ok(PathBuf::from(get_config().workspace_dir).join(format!("{}.json", name)))
+}
+
+#[auto_context::auto_context]
+pub fn save_workspace(name: &str, workspace: &Workspace) -> Result<String> {
+    let path = workspace_path(name)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let path = path.to_string_lossy().to_string();
+    write_to_file(&serde_json::to_string_pretty(workspace)?, &path)?;
+    Ok(path)
+}
+
+#[auto_context::auto_context]
+pub fn read_workspace(name: &str) -> Result<Workspace> {
+    let path = workspace_path(name)?;
+    let raw = std::fs::read_to_string(&path)?;
+    let workspace = serde_json::from_str(&raw)?;
+    Ok(workspace)
+}